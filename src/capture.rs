@@ -0,0 +1,178 @@
+use std::str::FromStr;
+
+use super::AxisThreshold;
+use super::ControlBind;
+use super::ElementState;
+use super::FireTrigger;
+use super::HoldableTrigger;
+use super::InputEvent;
+use super::MouseWheelDirection;
+use super::Target;
+use super::TargetKind;
+use super::ThresholdAxis;
+use super::ValueTrigger;
+
+#[derive(Debug, Clone)]
+pub struct CaptureOptions {
+    // dispatched as usual instead of being captured, e.g. Escape to cancel
+    pub ignore: Vec<HoldableTrigger>,
+    // only captures triggers that can be bound to targets of this kind
+    pub kind: Option<TargetKind>,
+    // how far an axis has to move to be captured
+    pub axis_threshold: f64,
+    // whether Motion events are captured, mice report them on every move like any other axis
+    pub raw_axes: bool,
+}
+
+impl Default for CaptureOptions {
+    fn default() -> Self {
+        CaptureOptions {
+            ignore: Vec::new(),
+            kind: None,
+            axis_threshold: 0.5,
+            raw_axes: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CapturedTrigger {
+    Holdable(HoldableTrigger),
+    MouseWheelTick(MouseWheelDirection),
+    Value(ValueTrigger),
+}
+
+impl CapturedTrigger {
+    // None if the trigger can't be bound to the target
+    pub fn to_bind<FireTarget, SwitchTarget, ValueTarget>(
+        self,
+        target: Target<FireTarget, SwitchTarget, ValueTarget>,
+    ) -> Option<ControlBind<FireTarget, SwitchTarget, ValueTarget>>
+    where FireTarget: FromStr,
+          SwitchTarget: FromStr,
+          ValueTarget: FromStr,
+    {
+        use self::CapturedTrigger::*;
+
+        match (self, target) {
+            (Holdable(trigger), Target::Fire(target)) => Some(ControlBind::Fire(FireTrigger::Holdable(trigger), target)),
+            (MouseWheelTick(direction), Target::Fire(target)) =>
+                Some(ControlBind::Fire(FireTrigger::MouseWheelTick(direction), target)),
+            (Holdable(trigger), Target::Switch(target)) => Some(ControlBind::Switch(trigger, target)),
+            (Value(trigger), Target::Value(target)) => Some(ControlBind::Value(trigger, target)),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) struct Capture {
+    options: CaptureOptions,
+    // presses since the capture started, they become a chord if there is more than one
+    pressed: Vec<HoldableTrigger>,
+    pub(crate) result: Option<CapturedTrigger>,
+}
+
+impl Capture {
+    pub(crate) fn new(options: CaptureOptions) -> Self {
+        Capture {
+            options,
+            pressed: Vec::new(),
+            result: None,
+        }
+    }
+
+    // returns whether the event was used up, releases never are,
+    // so that triggers pressed before the capture don't get stuck
+    pub(crate) fn on_event(&mut self, event: &InputEvent) -> bool {
+        use self::InputEvent::*;
+        use self::TargetKind::*;
+
+        if self.result.is_some() {
+            return false;
+        }
+        let kind = self.options.kind;
+        let threshold = self.options.axis_threshold;
+        match *event {
+            Key { scan_code, key_code, state } => {
+                let scan_code = HoldableTrigger::ScanCode(scan_code);
+                if self.options.ignore.contains(&scan_code) {
+                    return false;
+                }
+                self.on_holdable(key_code.map(HoldableTrigger::KeyCode).unwrap_or(scan_code), state)
+            },
+            Button { button, state } => self.on_holdable(HoldableTrigger::Button(button), state),
+            GamepadButton { button, state } => self.on_holdable(HoldableTrigger::GamepadButton(button), state),
+            MouseWheel { delta } => {
+                let direction = if delta < 0.0 {
+                    MouseWheelDirection::Up
+                } else if delta > 0.0 {
+                    MouseWheelDirection::Down
+                } else {
+                    return true;
+                };
+                self.result = match kind {
+                    None | Some(Fire) => Some(CapturedTrigger::MouseWheelTick(direction)),
+                    Some(Value) => Some(CapturedTrigger::Value(ValueTrigger::MouseWheel)),
+                    Some(Switch) => None,
+                };
+                true
+            },
+            // the mouse moves by accident too often, so it's only captured when asked for
+            MouseMotion { delta: (dx, dy) } => {
+                if kind == Some(Value) && dx.abs().max(dy.abs()) >= threshold {
+                    let trigger = if dx.abs() >= dy.abs() { ValueTrigger::MouseX } else { ValueTrigger::MouseY };
+                    self.result = Some(CapturedTrigger::Value(trigger));
+                }
+                true
+            },
+            Motion { axis, value } => {
+                if self.options.raw_axes && value.abs() >= threshold {
+                    self.result = match kind {
+                        None | Some(Value) => Some(CapturedTrigger::Value(ValueTrigger::Axis(axis))),
+                        Some(Fire) | Some(Switch) =>
+                            Some(self.axis_threshold(ThresholdAxis::Axis(axis), value)),
+                    };
+                }
+                true
+            },
+            GamepadAxis { axis, value } => {
+                if value.abs() >= threshold {
+                    self.result = match kind {
+                        None | Some(Value) => Some(CapturedTrigger::Value(ValueTrigger::GamepadAxis(axis))),
+                        Some(Fire) | Some(Switch) =>
+                            Some(self.axis_threshold(ThresholdAxis::GamepadAxis(axis), value)),
+                    };
+                }
+                true
+            },
+            Removed => false,
+        }
+    }
+
+    fn axis_threshold(&self, axis: ThresholdAxis, value: f64) -> CapturedTrigger {
+        let threshold = self.options.axis_threshold.copysign(value);
+        CapturedTrigger::Holdable(HoldableTrigger::AxisThreshold(AxisThreshold { axis, threshold, hysteresis: 0.0 }))
+    }
+
+    fn on_holdable(&mut self, trigger: HoldableTrigger, state: ElementState) -> bool {
+        if self.options.ignore.contains(&trigger) {
+            return false;
+        }
+        match state {
+            ElementState::Pressed => {
+                if self.options.kind != Some(TargetKind::Value) && !self.pressed.contains(&trigger) {
+                    self.pressed.push(trigger);
+                }
+                true
+            },
+            ElementState::Released => {
+                // the first release ends the capture, with everything that is held at that point
+                if self.pressed.contains(&trigger) {
+                    let trigger = HoldableTrigger::chord(self.pressed.drain(..)).unwrap_or(trigger);
+                    self.result = Some(CapturedTrigger::Holdable(trigger));
+                }
+                false
+            },
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use super::ControlBind;
+use super::FireTrigger;
+use super::HoldableTrigger;
+use super::ValueTrigger;
+use super::VectorTrigger;
+
+// a named set of binds that can be pushed onto the context stack,
+// e.g. for a menu or a vehicle that need other binds than the rest of the game
+#[derive(Debug, Clone)]
+pub struct Context<FireTarget, SwitchTarget, ValueTarget> {
+    pub binds: Vec<ControlBind<FireTarget, SwitchTarget, ValueTarget>>,
+    // triggers bound in this context don't reach the contexts below it
+    pub consume: bool,
+}
+
+impl<FireTarget, SwitchTarget, ValueTarget> Context<FireTarget, SwitchTarget, ValueTarget> {
+    pub fn new(consume: bool) -> Self {
+        Context {
+            binds: Vec::new(),
+            consume,
+        }
+    }
+}
+
+// what a bind listens to, so that binds of different kinds on the same key consume each other
+#[derive(Debug, PartialEq)]
+pub(crate) enum BindInput<'a> {
+    Holdable(&'a HoldableTrigger),
+    Fire(&'a FireTrigger),
+    Value(&'a ValueTrigger),
+    Vector(&'a VectorTrigger),
+}
+
+impl<FireTarget, SwitchTarget, ValueTarget> ControlBind<FireTarget, SwitchTarget, ValueTarget> {
+    // every holdable trigger the bind reads, including the components of chords, composites and so on
+    pub(crate) fn inputs(&self) -> Vec<BindInput<'_>> {
+        use self::ControlBind::*;
+        use self::FireTrigger::*;
+
+        fn push_holdable<'a>(inputs: &mut Vec<BindInput<'a>>, trigger: &'a HoldableTrigger) {
+            inputs.push(BindInput::Holdable(trigger));
+            if let HoldableTrigger::Chord(components) = trigger {
+                inputs.extend(components.iter().map(BindInput::Holdable));
+            }
+        }
+
+        let mut inputs = Vec::new();
+        match self {
+            Fire(Holdable(trigger), _)
+            | Fire(Release(trigger), _)
+            | Fire(Tap { trigger, .. }, _)
+            | Fire(LongPress { trigger, .. }, _)
+            | Fire(Repeat { trigger, .. }, _)
+            | Switch(trigger, _)
+            | Toggle(trigger, _) => push_holdable(&mut inputs, trigger),
+            Fire(Sequence { steps, .. }, _) => for step in steps {
+                push_holdable(&mut inputs, step);
+            },
+            Fire(trigger, _) => inputs.push(BindInput::Fire(trigger)),
+            Value(ValueTrigger::Composite { negative, positive, .. }, _) => {
+                push_holdable(&mut inputs, negative);
+                push_holdable(&mut inputs, positive);
+            },
+            Value(trigger, _) => inputs.push(BindInput::Value(trigger)),
+            Vector(VectorTrigger::Keys { up, down, left, right }, _) => for trigger in [up, down, left, right].iter() {
+                push_holdable(&mut inputs, trigger);
+            },
+            Vector(trigger, _) => inputs.push(BindInput::Vector(trigger)),
+        }
+        inputs
+    }
+}
@@ -0,0 +1,97 @@
+use std::error::Error;
+use std::fmt;
+
+use toml::Value;
+
+use super::TargetKind;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlsError {
+    ExpectedTable { path: String, value: Value },
+    MissingSection { path: String },
+    UnknownTarget { path: String, name: String },
+    WrongTargetKind { path: String, name: String, expected: TargetKind },
+    UnknownTrigger { path: String, value: Value },
+    InvalidId { path: String, value: Value },
+    InvalidFactor { path: String, value: Value },
+    InvalidProcessing { path: String, value: Value },
+    InvalidOption { path: String, value: Value },
+    InvalidDuration { path: String, value: Value },
+}
+
+impl ControlsError {
+    // the toml path of the offending entry, e.g. binds.Jump
+    pub fn path(&self) -> &str {
+        use self::ControlsError::*;
+
+        match self {
+            ExpectedTable { path, .. }
+            | MissingSection { path }
+            | UnknownTarget { path, .. }
+            | WrongTargetKind { path, .. }
+            | UnknownTrigger { path, .. }
+            | InvalidId { path, .. }
+            | InvalidFactor { path, .. }
+            | InvalidProcessing { path, .. }
+            | InvalidOption { path, .. }
+            | InvalidDuration { path, .. } => path,
+        }
+    }
+
+    // trigger and target parsers don't know where their input came from,
+    // so they report an empty path and the caller prefixes it
+    pub(crate) fn in_path(mut self, prefix: &str) -> Self {
+        let path = self.path_mut();
+        *path = if path.is_empty() {
+            String::from(prefix)
+        } else if path.starts_with('[') {
+            format!("{}{}", prefix, path)
+        } else {
+            format!("{}.{}", prefix, path)
+        };
+        self
+    }
+
+    fn path_mut(&mut self) -> &mut String {
+        use self::ControlsError::*;
+
+        match self {
+            ExpectedTable { path, .. }
+            | MissingSection { path }
+            | UnknownTarget { path, .. }
+            | WrongTargetKind { path, .. }
+            | UnknownTrigger { path, .. }
+            | InvalidId { path, .. }
+            | InvalidFactor { path, .. }
+            | InvalidProcessing { path, .. }
+            | InvalidOption { path, .. }
+            | InvalidDuration { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for ControlsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::ControlsError::*;
+
+        if !self.path().is_empty() {
+            write!(f, "{}: ", self.path())?;
+        }
+        match self {
+            ExpectedTable { value, .. } => write!(f, "Expected a table, got '{}'!", value),
+            MissingSection { .. } => write!(f, "Section is missing!"),
+            UnknownTarget { name, .. } => write!(f, "Unknown target '{}'!", name),
+            WrongTargetKind { name, expected, .. } =>
+                write!(f, "Expected {} target, got '{}'!", expected, name),
+            UnknownTrigger { value, .. } => write!(f, "Unknown trigger '{}'!", value),
+            InvalidId { value, .. } => write!(f, "Invalid id '{}'!", value),
+            InvalidFactor { value, .. } => write!(f, "Factor must be a float, got '{}'!", value),
+            InvalidProcessing { value, .. } => write!(f, "Invalid processing setting '{}'!", value),
+            InvalidOption { value, .. } => write!(f, "Invalid option '{}'!", value),
+            InvalidDuration { value, .. } =>
+                write!(f, "Duration must be a non-negative number of milliseconds, got '{}'!", value),
+        }
+    }
+}
+
+impl Error for ControlsError {}
@@ -0,0 +1,60 @@
+// the layout follows the usual xbox style pad, "South" is A on xbox and cross on playstation pads
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    Mode,
+    LeftThumb,
+    RightThumb,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+pub(crate) const GAMEPAD_BUTTON_PAIRS: &[(GamepadButton, &str)] = &[
+    (GamepadButton::South, "GamepadSouth"),
+    (GamepadButton::East, "GamepadEast"),
+    (GamepadButton::North, "GamepadNorth"),
+    (GamepadButton::West, "GamepadWest"),
+    (GamepadButton::LeftBumper, "GamepadLeftBumper"),
+    (GamepadButton::RightBumper, "GamepadRightBumper"),
+    (GamepadButton::LeftTrigger, "GamepadLeftTrigger"),
+    (GamepadButton::RightTrigger, "GamepadRightTrigger"),
+    (GamepadButton::Select, "GamepadSelect"),
+    (GamepadButton::Start, "GamepadStart"),
+    (GamepadButton::Mode, "GamepadMode"),
+    (GamepadButton::LeftThumb, "GamepadLeftThumb"),
+    (GamepadButton::RightThumb, "GamepadRightThumb"),
+    (GamepadButton::DPadUp, "GamepadDPadUp"),
+    (GamepadButton::DPadDown, "GamepadDPadDown"),
+    (GamepadButton::DPadLeft, "GamepadDPadLeft"),
+    (GamepadButton::DPadRight, "GamepadDPadRight"),
+];
+
+pub(crate) const GAMEPAD_AXIS_PAIRS: &[(GamepadAxis, &str)] = &[
+    (GamepadAxis::LeftStickX, "LeftStickX"),
+    (GamepadAxis::LeftStickY, "LeftStickY"),
+    (GamepadAxis::RightStickX, "RightStickX"),
+    (GamepadAxis::RightStickY, "RightStickY"),
+    (GamepadAxis::LeftTrigger, "LeftTrigger"),
+    (GamepadAxis::RightTrigger, "RightTrigger"),
+];
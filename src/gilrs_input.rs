@@ -0,0 +1,97 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::str::FromStr;
+use std::string::ToString;
+
+use gilrs::Axis;
+use gilrs::Button;
+use gilrs::EventType;
+
+use super::Controls;
+use super::DeviceId;
+use super::ElementState;
+use super::GamepadAxis;
+use super::GamepadButton;
+use super::InputEvent;
+use super::ValueTargetTrait;
+
+impl From<gilrs::GamepadId> for DeviceId {
+    fn from(gamepad_id: gilrs::GamepadId) -> Self {
+        // hashed together with a tag, so the ids can't collide with the ones of other backends
+        let mut hasher = DefaultHasher::new();
+        ("gilrs", usize::from(gamepad_id)).hash(&mut hasher);
+        DeviceId(hasher.finish())
+    }
+}
+
+fn button_from_gilrs(button: Button) -> Option<GamepadButton> {
+    match button {
+        Button::South => Some(GamepadButton::South),
+        Button::East => Some(GamepadButton::East),
+        Button::North => Some(GamepadButton::North),
+        Button::West => Some(GamepadButton::West),
+        Button::LeftTrigger => Some(GamepadButton::LeftBumper),
+        Button::RightTrigger => Some(GamepadButton::RightBumper),
+        Button::LeftTrigger2 => Some(GamepadButton::LeftTrigger),
+        Button::RightTrigger2 => Some(GamepadButton::RightTrigger),
+        Button::Select => Some(GamepadButton::Select),
+        Button::Start => Some(GamepadButton::Start),
+        Button::Mode => Some(GamepadButton::Mode),
+        Button::LeftThumb => Some(GamepadButton::LeftThumb),
+        Button::RightThumb => Some(GamepadButton::RightThumb),
+        Button::DPadUp => Some(GamepadButton::DPadUp),
+        Button::DPadDown => Some(GamepadButton::DPadDown),
+        Button::DPadLeft => Some(GamepadButton::DPadLeft),
+        Button::DPadRight => Some(GamepadButton::DPadRight),
+        _ => None,
+    }
+}
+
+impl InputEvent {
+    pub fn from_gilrs(event_type: EventType) -> Option<Self> {
+        match event_type {
+            EventType::ButtonPressed(button, _) => Some(InputEvent::GamepadButton {
+                button: button_from_gilrs(button)?,
+                state: ElementState::Pressed,
+            }),
+            EventType::ButtonReleased(button, _) => Some(InputEvent::GamepadButton {
+                button: button_from_gilrs(button)?,
+                state: ElementState::Released,
+            }),
+            // the analog triggers report their position as button value
+            EventType::ButtonChanged(Button::LeftTrigger2, value, _) => Some(InputEvent::GamepadAxis {
+                axis: GamepadAxis::LeftTrigger,
+                value: value as f64,
+            }),
+            EventType::ButtonChanged(Button::RightTrigger2, value, _) => Some(InputEvent::GamepadAxis {
+                axis: GamepadAxis::RightTrigger,
+                value: value as f64,
+            }),
+            EventType::AxisChanged(axis, value, _) => {
+                let axis = match axis {
+                    Axis::LeftStickX => GamepadAxis::LeftStickX,
+                    Axis::LeftStickY => GamepadAxis::LeftStickY,
+                    Axis::RightStickX => GamepadAxis::RightStickX,
+                    Axis::RightStickY => GamepadAxis::RightStickY,
+                    _ => return None,
+                };
+                Some(InputEvent::GamepadAxis { axis, value: value as f64 })
+            },
+            EventType::Disconnected => Some(InputEvent::Removed),
+            _ => None,
+        }
+    }
+}
+
+impl<FireTarget, SwitchTarget, ValueTarget> Controls<FireTarget, SwitchTarget, ValueTarget>
+where FireTarget: Copy + Eq + Hash + FromStr + ToString,
+      SwitchTarget: Copy + Eq + Hash + FromStr + ToString,
+      ValueTarget: ValueTargetTrait + Copy + Eq + Hash + FromStr + ToString,
+{
+    pub fn process_gilrs(&mut self, event: gilrs::Event) {
+        if let Some(input_event) = InputEvent::from_gilrs(event.event) {
+            self.process(event.id.into(), input_event);
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use super::VirtualKeyCode;
+use super::GamepadAxis;
+use super::GamepadButton;
+
+// backends map their own device ids onto these, tests can simply make some up
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DeviceId(pub u64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementState {
+    Pressed,
+    Released,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputEvent {
+    Key { scan_code: u32, key_code: Option<VirtualKeyCode>, state: ElementState },
+    Button { button: u32, state: ElementState },
+    MouseWheel { delta: f64 },
+    MouseMotion { delta: (f64, f64) },
+    Motion { axis: u32, value: f64 },
+    GamepadButton { button: GamepadButton, state: ElementState },
+    GamepadAxis { axis: GamepadAxis, value: f64 },
+    Removed,
+}
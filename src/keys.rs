@@ -0,0 +1,186 @@
+// the names are used in config files, so they must not change
+macro_rules! key_codes {
+    ($($key:ident => $name:expr,)*) => {
+        #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+        pub enum VirtualKeyCode {
+            $($key,)*
+        }
+
+        pub(crate) const KEY_CODE_PAIRS: &[(VirtualKeyCode, &str)] = &[
+            $((VirtualKeyCode::$key, $name),)*
+        ];
+
+        #[cfg(feature = "winit")]
+        impl From<winit::event::VirtualKeyCode> for VirtualKeyCode {
+            fn from(key_code: winit::event::VirtualKeyCode) -> Self {
+                match key_code {
+                    $(winit::event::VirtualKeyCode::$key => VirtualKeyCode::$key,)*
+                }
+            }
+        }
+    };
+}
+
+key_codes! {
+    Key1 => "1",
+    Key2 => "2",
+    Key3 => "3",
+    Key4 => "4",
+    Key5 => "5",
+    Key6 => "6",
+    Key7 => "7",
+    Key8 => "8",
+    Key9 => "9",
+    Key0 => "0",
+    A => "A",
+    B => "B",
+    C => "C",
+    D => "D",
+    E => "E",
+    F => "F",
+    G => "G",
+    H => "H",
+    I => "I",
+    J => "J",
+    K => "K",
+    L => "L",
+    M => "M",
+    N => "N",
+    O => "O",
+    P => "P",
+    Q => "Q",
+    R => "R",
+    S => "S",
+    T => "T",
+    U => "U",
+    V => "V",
+    W => "W",
+    X => "X",
+    Y => "Y",
+    Z => "Z",
+    Escape => "Escape",
+    F1 => "F1",
+    F2 => "F2",
+    F3 => "F3",
+    F4 => "F4",
+    F5 => "F5",
+    F6 => "F6",
+    F7 => "F7",
+    F8 => "F8",
+    F9 => "F9",
+    F10 => "F10",
+    F11 => "F11",
+    F12 => "F12",
+    F13 => "F13",
+    F14 => "F14",
+    F15 => "F15",
+    F16 => "F16",
+    F17 => "F17",
+    F18 => "F18",
+    F19 => "F19",
+    F20 => "F20",
+    F21 => "F21",
+    F22 => "F22",
+    F23 => "F23",
+    F24 => "F24",
+    Snapshot => "Snapshot",
+    Scroll => "Scroll",
+    Pause => "Pause",
+    Insert => "Insert",
+    Home => "Home",
+    Delete => "Delete",
+    End => "End",
+    PageDown => "PageDown",
+    PageUp => "PageUp",
+    Left => "Left",
+    Up => "Up",
+    Right => "Right",
+    Down => "Down",
+    Back => "Back",
+    Return => "Return",
+    Space => "Space",
+    Compose => "Compose",
+    Caret => "Caret",
+    Numlock => "Numlock",
+    Numpad0 => "Numpad0",
+    Numpad1 => "Numpad1",
+    Numpad2 => "Numpad2",
+    Numpad3 => "Numpad3",
+    Numpad4 => "Numpad4",
+    Numpad5 => "Numpad5",
+    Numpad6 => "Numpad6",
+    Numpad7 => "Numpad7",
+    Numpad8 => "Numpad8",
+    Numpad9 => "Numpad9",
+    AbntC1 => "AbntC1",
+    AbntC2 => "AbntC2",
+    Add => "Add",
+    Apostrophe => "Apostrophe",
+    Apps => "Apps",
+    At => "At",
+    Ax => "Ax",
+    Backslash => "Backslash",
+    Calculator => "Calculator",
+    Capital => "Capital",
+    Colon => "Colon",
+    Comma => "Comma",
+    Convert => "Convert",
+    Decimal => "Decimal",
+    Divide => "Divide",
+    Equals => "Equals",
+    Grave => "Grave",
+    Kana => "Kana",
+    Kanji => "Kanji",
+    LAlt => "LAlt",
+    LBracket => "LBracket",
+    LControl => "LControl",
+    LShift => "LShift",
+    LWin => "LWin",
+    Mail => "Mail",
+    MediaSelect => "MediaSelect",
+    MediaStop => "MediaStop",
+    Minus => "Minus",
+    Multiply => "Multiply",
+    Mute => "Mute",
+    MyComputer => "MyComputer",
+    NavigateForward => "NavigateForward",
+    NavigateBackward => "NavigateBackward",
+    NextTrack => "NextTrack",
+    NoConvert => "NoConvert",
+    NumpadComma => "NumpadComma",
+    NumpadEnter => "NumpadEnter",
+    NumpadEquals => "NumpadEquals",
+    OEM102 => "OEM102",
+    Period => "Period",
+    PlayPause => "PlayPause",
+    Power => "Power",
+    PrevTrack => "PrevTrack",
+    RAlt => "RAlt",
+    RBracket => "RBracket",
+    RControl => "RControl",
+    RShift => "RShift",
+    RWin => "RWin",
+    Semicolon => "Semicolon",
+    Slash => "Slash",
+    Sleep => "Sleep",
+    Stop => "Stop",
+    Subtract => "Subtract",
+    Sysrq => "Sysrq",
+    Tab => "Tab",
+    Underline => "Underline",
+    Unlabeled => "Unlabeled",
+    VolumeDown => "VolumeDown",
+    VolumeUp => "VolumeUp",
+    Wake => "Wake",
+    WebBack => "WebBack",
+    WebFavorites => "WebFavorites",
+    WebForward => "WebForward",
+    WebHome => "WebHome",
+    WebRefresh => "WebRefresh",
+    WebSearch => "WebSearch",
+    WebStop => "WebStop",
+    Yen => "Yen",
+    Copy => "Copy",
+    Paste => "Paste",
+    Cut => "Cut",
+}
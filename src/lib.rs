@@ -1,4 +1,20 @@
 mod triggers;
+mod error;
+mod keys;
+mod input;
+mod output;
+mod gamepad;
+mod processing;
+mod context;
+mod capture;
+mod recording;
+mod scope;
+#[cfg(feature = "winit")]
+mod winit_input;
+#[cfg(feature = "gilrs")]
+mod gilrs_input;
+#[cfg(feature = "serde")]
+mod serde_impls;
 
 use std::collections::VecDeque;
 use std::collections::HashMap;
@@ -7,18 +23,36 @@ use std::collections::BTreeMap;
 use std::string::ToString;
 use std::hash::Hash;
 use std::str::FromStr;
+use std::fmt;
+use std::time::Duration;
+use std::iter;
 
-use winit::event::ElementState;
-use winit::event::ButtonId;
-use winit::event::MouseScrollDelta;
-use winit::event::DeviceId;
-use winit::event::DeviceEvent;
-use winit::event::KeyboardInput;
+use self::output::Output;
+use self::capture::Capture;
 
 pub use self::triggers::FireTrigger;
 pub use self::triggers::HoldableTrigger;
 pub use self::triggers::ValueTrigger;
-pub use winit::event::VirtualKeyCode;
+pub use self::triggers::AxisThreshold;
+pub use self::triggers::ThresholdAxis;
+pub use self::triggers::VectorTrigger;
+pub use self::error::ControlsError;
+pub use self::keys::VirtualKeyCode;
+pub use self::input::DeviceId;
+pub use self::input::ElementState;
+pub use self::input::InputEvent;
+pub use self::gamepad::GamepadButton;
+pub use self::gamepad::GamepadAxis;
+pub use self::processing::ValueProcessing;
+pub use self::processing::ResponseCurve;
+pub use self::context::Context;
+pub use self::capture::CaptureOptions;
+pub use self::capture::CapturedTrigger;
+pub use self::recording::Recording;
+pub use self::recording::RecordedInput;
+pub use self::scope::BindScope;
+#[cfg(feature = "serde")]
+pub use self::serde_impls::ControlsSnapshot;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MouseWheelDirection {
@@ -32,10 +66,36 @@ pub enum SwitchState {
     Inactive,
 }
 
+// what happens to the held triggers when the controls are paused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseMode {
+    // they stay held, the changes during the pause are announced on resume
+    Freeze,
+    // they are forgotten, like after reset_held_state
+    Release,
+}
+
 pub trait ValueTargetTrait {
     fn base_factor(&self) -> f64;
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetKind {
+    Fire,
+    Switch,
+    Value,
+}
+
+impl fmt::Display for TargetKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TargetKind::Fire => write!(f, "fire"),
+            TargetKind::Switch => write!(f, "switch"),
+            TargetKind::Value => write!(f, "value"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Target<FireTarget, SwitchTarget, ValueTarget>
 where FireTarget: FromStr,
@@ -53,7 +113,7 @@ where FireTarget: FromStr,
       SwitchTarget: FromStr,
       ValueTarget: FromStr,
 {
-    type Err = String;
+    type Err = ControlsError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         use self::Target::*;
 
@@ -64,16 +124,19 @@ where FireTarget: FromStr,
         } else if let Ok(target) = s.parse::<ValueTarget>() {
             Ok(Value(target))
         } else {
-            Err(format!("Unknown target '{}'!", s))
+            Err(ControlsError::UnknownTarget { path: String::new(), name: String::from(s) })
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ControlBind<FireTarget, SwitchTarget, ValueTarget> {
     Fire(FireTrigger, FireTarget),
     Switch(HoldableTrigger, SwitchTarget),
+    // every press turns the switch on or off
+    Toggle(HoldableTrigger, SwitchTarget),
     Value(ValueTrigger, ValueTarget),
+    Vector(VectorTrigger, ValueTarget),
 }
 
 #[derive(Debug, Default)]
@@ -82,9 +145,20 @@ where FireTarget: Eq + Hash,
       SwitchTarget: Eq + Hash,
 {
     on_press: HashSet<FireTarget>,
+    on_release: HashSet<FireTarget>,
+    on_tap: HashSet<(Duration, FireTarget)>,
+    on_long_press: HashSet<(Duration, FireTarget)>,
+    // (delay, interval, target)
+    on_repeat: HashSet<(Duration, Duration, FireTarget)>,
     while_down: HashSet<SwitchTarget>,
+    toggles: HashSet<SwitchTarget>,
+    // the subset of toggles that is turned on, each one holds the switch like a pressed trigger
+    toggled_on: HashSet<SwitchTarget>,
+    pressed_at: Duration,
     device_counters: HashMap<DeviceId, u32>,
     overall_counter: u32,
+    // the press completed a chord, so the release must not be dispatched either
+    suppressed: bool,
 }
 
 impl<FireTarget, SwitchTarget> HoldableTriggerData<FireTarget, SwitchTarget>
@@ -94,9 +168,103 @@ where FireTarget: Eq + Hash,
     fn new() -> Self {
         Self {
             on_press: HashSet::new(),
+            on_release: HashSet::new(),
+            on_tap: HashSet::new(),
+            on_long_press: HashSet::new(),
+            on_repeat: HashSet::new(),
             while_down: HashSet::new(),
+            toggles: HashSet::new(),
+            toggled_on: HashSet::new(),
+            pressed_at: Duration::from_secs(0),
             device_counters: HashMap::new(),
             overall_counter: 0,
+            suppressed: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct SequenceData<FireTarget>
+where FireTarget: Eq + Hash,
+{
+    on_complete: HashSet<FireTarget>,
+    // number of steps already pressed
+    progress: usize,
+    last_step_time: Duration,
+}
+
+impl<FireTarget> SequenceData<FireTarget>
+where FireTarget: Eq + Hash,
+{
+    fn new() -> Self {
+        Self {
+            on_complete: HashSet::new(),
+            progress: 0,
+            last_step_time: Duration::from_secs(0),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CompositeData<ValueTarget>
+where ValueTarget: Eq + Hash,
+{
+    targets: HashSet<ValueTarget>,
+    value: f64,
+    // the value changed while it couldn't be announced
+    stale: bool,
+}
+
+impl<ValueTarget> CompositeData<ValueTarget>
+where ValueTarget: Eq + Hash,
+{
+    fn new() -> Self {
+        Self {
+            targets: HashSet::new(),
+            value: 0.0,
+            stale: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct VectorData<ValueTarget>
+where ValueTarget: Eq + Hash,
+{
+    targets: HashSet<ValueTarget>,
+    // the last value, for triggers that report the components one by one
+    value: (f64, f64),
+    // the value of a key vector changed while it couldn't be announced
+    stale: bool,
+}
+
+impl<ValueTarget> VectorData<ValueTarget>
+where ValueTarget: Eq + Hash,
+{
+    fn new() -> Self {
+        Self {
+            targets: HashSet::new(),
+            value: (0.0, 0.0),
+            stale: false,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct MouseMotionMapping<ValueTarget>
+where ValueTarget: Eq + Hash,
+{
+    on_x: HashSet<ValueTarget>,
+    on_y: HashSet<ValueTarget>,
+}
+
+impl<ValueTarget> MouseMotionMapping<ValueTarget>
+where ValueTarget: Eq + Hash,
+{
+    fn new() -> Self {
+        Self {
+            on_x: HashSet::new(),
+            on_y: HashSet::new(),
         }
     }
 }
@@ -124,11 +292,18 @@ where FireTarget: Eq + Hash,
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ControlEvent<FireTarget, SwitchTarget, ValueTarget> {
     Fire(FireTarget),
     Switch { target: SwitchTarget, state: SwitchState },
     Value { target: ValueTarget, value: f64 },
+    Vector { target: ValueTarget, value: (f64, f64) },
+    // only for automatic assignments
+    DeviceAssigned { device_id: DeviceId, slot: u32 },
+    // the device was removed while it was assigned to the slot
+    DeviceUnassigned { device_id: DeviceId, slot: u32 },
+    // after everything the device held was released
+    DeviceDisconnected(DeviceId),
 }
 
 pub struct SwitchCounter {
@@ -143,11 +318,53 @@ where FireTarget: Eq + Hash,
 {
     holdable_trigger_data: HashMap<HoldableTrigger, HoldableTriggerData<FireTarget, SwitchTarget>>,
     axis_mappings: HashMap<u32, HashSet<ValueTarget>>,
+    gamepad_axis_mappings: HashMap<GamepadAxis, HashSet<ValueTarget>>,
+    mouse_motion_mapping: MouseMotionMapping<ValueTarget>,
     mouse_wheel_mapping: MouseWheelMapping<FireTarget, ValueTarget>,
-    switch_counters: HashMap<SwitchTarget, SwitchCounter>,
-    value_factors: HashMap<ValueTarget, f64>,
-    events: VecDeque<ControlEvent<FireTarget, SwitchTarget, ValueTarget>>,
-    paused: bool,
+    sequences: HashMap<(Vec<HoldableTrigger>, Duration), SequenceData<FireTarget>>,
+    // keyed by (negative, positive, ramp_up, ramp_down)
+    composites: HashMap<(HoldableTrigger, HoldableTrigger, Duration, Duration), CompositeData<ValueTarget>>,
+    vector_mappings: HashMap<VectorTrigger, VectorData<ValueTarget>>,
+    // the binds outside of any context, the bind maps above hold the ones that are in effect
+    base_binds: Vec<ControlBind<FireTarget, SwitchTarget, ValueTarget>>,
+    contexts: HashMap<String, Context<FireTarget, SwitchTarget, ValueTarget>>,
+    // the last context is the top one
+    context_stack: Vec<String>,
+    // each scope has its own trigger state, but they all share the output,
+    // contexts only apply to the unscoped binds
+    scoped_layers: BTreeMap<BindScope, Controls<FireTarget, SwitchTarget, ValueTarget>>,
+    device_slots: HashMap<DeviceId, u32>,
+    // the first press of an unassigned device assigns it to the first empty slot below this
+    auto_assign_slots: u32,
+    suppress_chord_components: bool,
+    pause_mode: PauseMode,
+    // advanced by tick
+    time: Duration,
+    // the axis thresholds that are currently reached, per device
+    reached_axis_thresholds: HashSet<(AxisThreshold, DeviceId)>,
+    capture: Option<Capture>,
+    recording: Option<Recording>,
+    // the triggers that went down during the current input event, one key press can activate several,
+    // e.g. its key code and its scan code
+    pressed_triggers: Vec<HoldableTrigger>,
+    output: Output<FireTarget, SwitchTarget, ValueTarget>,
+}
+
+impl<FireTarget, SwitchTarget, ValueTarget> Default for Controls<FireTarget, SwitchTarget, ValueTarget>
+where FireTarget: Copy + Eq + Hash + FromStr + ToString,
+      SwitchTarget: Copy + Eq + Hash + FromStr + ToString,
+      ValueTarget: ValueTargetTrait + Copy + Eq + Hash + FromStr + ToString,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<FireTarget, SwitchTarget, ValueTarget> ControlBind<FireTarget, SwitchTarget, ValueTarget> {
+    // the config can't express them, so they aren't added
+    fn is_empty_sequence(&self) -> bool {
+        matches!(self, ControlBind::Fire(FireTrigger::Sequence { steps, .. }, _) if steps.is_empty())
+    }
 }
 
 impl<FireTarget, SwitchTarget, ValueTarget> Controls<FireTarget, SwitchTarget, ValueTarget>
@@ -159,165 +376,999 @@ where FireTarget: Copy + Eq + Hash + FromStr + ToString,
         Controls {
             holdable_trigger_data: HashMap::new(),
             axis_mappings: HashMap::new(),
+            gamepad_axis_mappings: HashMap::new(),
+            mouse_motion_mapping: MouseMotionMapping::new(),
             mouse_wheel_mapping: MouseWheelMapping::new(),
-            switch_counters: HashMap::new(),
-            value_factors: HashMap::new(),
-            events: VecDeque::new(),
-            paused: false,
+            sequences: HashMap::new(),
+            composites: HashMap::new(),
+            vector_mappings: HashMap::new(),
+            base_binds: Vec::new(),
+            contexts: HashMap::new(),
+            context_stack: Vec::new(),
+            scoped_layers: BTreeMap::new(),
+            device_slots: HashMap::new(),
+            auto_assign_slots: 0,
+            reached_axis_thresholds: HashSet::new(),
+            capture: None,
+            recording: None,
+            suppress_chord_components: false,
+            pause_mode: PauseMode::Freeze,
+            time: Duration::from_secs(0),
+            pressed_triggers: Vec::new(),
+            output: Output::new(),
         }
     }
 
-    pub fn from_toml(value: &toml::value::Value) -> Result<Self, String> {
-        use self::ControlBind::*;
+    pub fn from_toml(value: &toml::value::Value) -> Result<Self, ControlsError> {
+        Self::load_toml(value, &mut Err)
+    }
+
+    // skips invalid entries instead of failing, returns one warning per skipped entry
+    pub fn from_toml_lenient(value: &toml::value::Value) -> (Self, Vec<ControlsError>) {
+        let mut warnings = Vec::new();
+        let controls = Self::load_toml(value, &mut |error| {
+            warnings.push(error);
+            Ok(())
+        });
+        match controls {
+            Ok(controls) => (controls, warnings),
+            Err(_) => unreachable!("lenient loading never fails"),
+        }
+    }
+
+    // `on_error` decides whether an error aborts loading or whether the entry is skipped
+    fn load_toml(
+        value: &toml::value::Value,
+        on_error: &mut dyn FnMut(ControlsError) -> Result<(), ControlsError>,
+    ) -> Result<Self, ControlsError> {
         use toml::Value::Table;
         use toml::Value::Float;
+        use toml::Value::Boolean;
+        use toml::Value::Integer;
 
         let mut controls = Controls::new();
         let table = match value {
-            &Table(ref t) => t,
-            _ => return Err(String::from("Controls must be a table!")),
+            Table(t) => t,
+            v => {
+                on_error(ControlsError::ExpectedTable { path: String::new(), value: v.clone() })?;
+                return Ok(controls);
+            },
         };
 
         match table.get("binds") {
-            Some(v) => match v {
-                &Table(ref keys) => for (target_string, trigger_value) in keys {
-                    let bind = match target_string.parse()? {
-                        Target::Fire(target) =>
-                            Fire(FireTrigger::from_toml(trigger_value)?, target),
-                        Target::Switch(target) =>
-                            Switch(HoldableTrigger::from_toml(trigger_value)?, target),
-                        Target::Value(target) =>
-                            Value(ValueTrigger::from_toml(trigger_value)?, target),
-                    };
-                    controls.add_bind(bind);
-                },
-                _ => return Err(String::from("Binds must be a table!")),
+            Some(v) => for bind in Self::binds_from_toml(v, "binds", on_error)? {
+                controls.add_bind(bind);
             },
-            None => return Err(String::from("No binds section found in controls!")),
+            None => on_error(ControlsError::MissingSection { path: String::from("binds") })?,
         }
         match table.get("factors") {
             Some(v) => match v {
-                &Table(ref factors) => for (target_string, trigger_value) in factors {
-                    match target_string.parse::<Target<FireTarget, SwitchTarget, ValueTarget>>()? {
-                        Target::Value(target) => match trigger_value {
-                            &Float(factor) => controls.set_factor(target, factor),
-                            v => return Err(format!("Factor must be a float, got '{}'!", v)),
+                Table(factors) => for (target_string, factor_value) in factors {
+                    let path = format!("factors.{}", target_string);
+                    let error = match target_string.parse::<Target<FireTarget, SwitchTarget, ValueTarget>>() {
+                        Ok(Target::Value(target)) => match factor_value {
+                            Float(factor) => {
+                                controls.set_factor(target, *factor);
+                                continue;
+                            },
+                            v => ControlsError::InvalidFactor { path, value: v.clone() },
                         }
-                        _ => return Err(format!("Expected value target!")),
+                        Ok(_) => ControlsError::WrongTargetKind {
+                            path,
+                            name: target_string.clone(),
+                            expected: TargetKind::Value,
+                        },
+                        Err(e) => e.in_path(&path),
                     };
+                    on_error(error)?;
                 },
-                _ => return Err(String::from("Binds must be a table!")),
+                v => on_error(ControlsError::ExpectedTable {
+                    path: String::from("factors"),
+                    value: v.clone(),
+                })?,
             },
-            None => return Err(String::from("No binds section found in controls!")),
+            None => on_error(ControlsError::MissingSection { path: String::from("factors") })?,
+        }
+        match table.get("processing") {
+            Some(Table(processing)) => for (target_string, processing_value) in processing {
+                let path = format!("processing.{}", target_string);
+                let error = match target_string.parse::<Target<FireTarget, SwitchTarget, ValueTarget>>() {
+                    Ok(Target::Value(target)) => match ValueProcessing::from_toml(processing_value) {
+                        Ok(processing) => {
+                            controls.set_processing(target, processing);
+                            continue;
+                        },
+                        Err(e) => e.in_path(&path),
+                    },
+                    Ok(_) => ControlsError::WrongTargetKind {
+                        path,
+                        name: target_string.clone(),
+                        expected: TargetKind::Value,
+                    },
+                    Err(e) => e.in_path(&path),
+                };
+                on_error(error)?;
+            },
+            Some(v) => on_error(ControlsError::ExpectedTable {
+                path: String::from("processing"),
+                value: v.clone(),
+            })?,
+            // optional, since older configs don't have it
+            None => (),
+        }
+        match table.get("options") {
+            Some(Table(options)) => for (name, option_value) in options {
+                let path = format!("options.{}", name);
+                match (name.as_ref(), option_value) {
+                    ("suppress_chord_components", &Boolean(suppress)) =>
+                        controls.set_suppress_chord_components(suppress),
+                    ("auto_assign_slots", &Integer(slots)) if slots >= 0 && slots <= i64::from(u32::MAX) =>
+                        controls.set_auto_assign_slots(slots as u32),
+                    ("pause_mode", toml::Value::String(mode)) if mode == "Freeze" =>
+                        controls.set_pause_mode(PauseMode::Freeze),
+                    ("pause_mode", toml::Value::String(mode)) if mode == "Release" =>
+                        controls.set_pause_mode(PauseMode::Release),
+                    (_, v) => on_error(ControlsError::InvalidOption { path, value: v.clone() })?,
+                }
+            },
+            Some(v) => on_error(ControlsError::ExpectedTable {
+                path: String::from("options"),
+                value: v.clone(),
+            })?,
+            None => (),
+        }
+        match table.get("contexts") {
+            Some(Table(contexts)) => for (name, context_value) in contexts {
+                let path = format!("contexts.{}", name);
+                let context_table = match context_value {
+                    Table(context_table) => context_table,
+                    v => {
+                        on_error(ControlsError::ExpectedTable { path, value: v.clone() })?;
+                        continue;
+                    },
+                };
+                let mut context = Context::new(false);
+                for (key, value) in context_table {
+                    let key_path = format!("{}.{}", path, key);
+                    match (key.as_ref(), value) {
+                        ("consume", &Boolean(consume)) => context.consume = consume,
+                        ("binds", v) => context.binds = Self::binds_from_toml(v, &key_path, on_error)?,
+                        (_, v) => on_error(ControlsError::InvalidOption { path: key_path, value: v.clone() })?,
+                    }
+                }
+                if !context_table.contains_key("binds") {
+                    on_error(ControlsError::MissingSection { path: format!("{}.binds", path) })?;
+                }
+                controls.set_context(name, context);
+            },
+            Some(v) => on_error(ControlsError::ExpectedTable {
+                path: String::from("contexts"),
+                value: v.clone(),
+            })?,
+            None => (),
+        }
+        match table.get("slots") {
+            Some(Table(slots)) => for (slot_string, binds_value) in slots {
+                let path = format!("slots.{}", slot_string);
+                let slot = match slot_string.parse() {
+                    Ok(slot) => slot,
+                    Err(_) => {
+                        on_error(ControlsError::InvalidId {
+                            path,
+                            value: toml::Value::String(slot_string.clone()),
+                        })?;
+                        continue;
+                    },
+                };
+                for bind in Self::binds_from_toml(binds_value, &path, on_error)? {
+                    controls.add_scoped_bind(BindScope::Slot(slot), bind);
+                }
+            },
+            Some(v) => on_error(ControlsError::ExpectedTable {
+                path: String::from("slots"),
+                value: v.clone(),
+            })?,
+            None => (),
         }
         Ok(controls)
     }
 
+    // `path` is where the table of binds is, e.g. `binds` or `contexts.Menu.binds`
+    fn binds_from_toml(
+        value: &toml::value::Value,
+        path: &str,
+        on_error: &mut dyn FnMut(ControlsError) -> Result<(), ControlsError>,
+    ) -> Result<Vec<ControlBind<FireTarget, SwitchTarget, ValueTarget>>, ControlsError> {
+        use self::ControlBind::*;
+        use toml::Value::Table;
+        use toml::Value::Array;
+
+        let mut binds = Vec::new();
+        let keys = match value {
+            Table(keys) => keys,
+            v => {
+                on_error(ControlsError::ExpectedTable { path: String::from(path), value: v.clone() })?;
+                return Ok(binds);
+            },
+        };
+        for (target_string, trigger_values) in keys {
+            let path = format!("{}.{}", path, target_string);
+            let target = match target_string.parse::<Target<FireTarget, SwitchTarget, ValueTarget>>() {
+                Ok(target) => target,
+                Err(e) => {
+                    on_error(e.in_path(&path))?;
+                    continue;
+                },
+            };
+            let trigger_values = match trigger_values {
+                Array(values) => values.iter().enumerate()
+                    .map(|(i, value)| (format!("{}[{}]", path, i), value))
+                    .collect(),
+                value => vec![(path, value)],
+            };
+            for (path, trigger_value) in trigger_values {
+                let (path, trigger_value) = match trigger_value {
+                    Table(wrapper) if wrapper.len() == 1 && wrapper.contains_key("trigger") =>
+                        (format!("{}.trigger", path), &wrapper["trigger"]),
+                    value => (path, value),
+                };
+                let bind = match target {
+                    Target::Fire(target) => FireTrigger::from_toml(trigger_value)
+                        .map(|trigger| Fire(trigger, target)),
+                    Target::Switch(target) => match trigger_value.get("toggle") {
+                        Some(toggle_value) => HoldableTrigger::from_toml(toggle_value)
+                            .map_err(|e| e.in_path("toggle"))
+                            .map(|trigger| Toggle(trigger, target)),
+                        None => HoldableTrigger::from_toml(trigger_value)
+                            .map(|trigger| Switch(trigger, target)),
+                    },
+                    // a vector trigger error with a path was a vector trigger with a typo
+                    Target::Value(target) => match VectorTrigger::from_toml(trigger_value) {
+                        Ok(trigger) => Ok(Vector(trigger, target)),
+                        Err(e) if !e.path().is_empty() => Err(e),
+                        Err(_) => ValueTrigger::from_toml(trigger_value)
+                            .map(|trigger| Value(trigger, target)),
+                    },
+                };
+                match bind {
+                    Ok(bind) => binds.push(bind),
+                    Err(e) => on_error(e.in_path(&path))?,
+                }
+            }
+        }
+        Ok(binds)
+    }
+
+    fn binds_to_toml(binds: &[ControlBind<FireTarget, SwitchTarget, ValueTarget>]) -> toml::value::Value {
+        use self::ControlBind::*;
+        use toml::Value::Table;
+        use toml::Value::Array;
+
+        let mut triggers = BTreeMap::new();
+        for bind in binds {
+            let (target, trigger) = match bind {
+                Fire(trigger, target) => (target.to_string(), trigger.to_toml()),
+                Switch(trigger, target) => (target.to_string(), trigger.to_toml()),
+                Toggle(trigger, target) => {
+                    let mut table = BTreeMap::new();
+                    table.insert(String::from("toggle"), trigger.to_toml());
+                    (target.to_string(), Table(table))
+                },
+                Value(trigger, target) => (target.to_string(), trigger.to_toml()),
+                Vector(trigger, target) => (target.to_string(), trigger.to_toml()),
+            };
+            triggers.entry(target).or_insert_with(Vec::new).push(trigger);
+        }
+        let mut table = BTreeMap::new();
+        for (target, mut trigger_values) in triggers {
+            let value = if trigger_values.len() == 1 {
+                trigger_values.pop().unwrap()
+            } else {
+                // toml arrays can't mix types, so the plain triggers are wrapped in tables if needed
+                let type_str = trigger_values[0].type_str();
+                if trigger_values.iter().any(|v| v.type_str() != type_str) {
+                    for value in trigger_values.iter_mut() {
+                        if !value.is_table() {
+                            let mut wrapper = BTreeMap::new();
+                            wrapper.insert(String::from("trigger"), value.clone());
+                            *value = Table(wrapper);
+                        }
+                    }
+                }
+                // sort for a stable output, since the binds are stored in hash maps
+                trigger_values.sort_by_key(|v| v.to_string());
+                Array(trigger_values)
+            };
+            table.insert(target, value);
+        }
+        Table(table)
+    }
+
     pub fn to_toml(&self) -> toml::value::Value {
+        use toml::Value::Table;
+        use toml::Value::Float;
+        use toml::Value::Boolean;
+        use toml::Value::Integer;
+
+        let mut factors = BTreeMap::new(); // TODO maybe just clone?
+        for (target, &factor) in self.output.value_factors.iter() {
+            factors.insert(target.to_string(), Float(factor));
+        }
+        let mut processing = BTreeMap::new();
+        for (target, target_processing) in self.output.value_processing.iter() {
+            processing.insert(target.to_string(), target_processing.to_toml());
+        }
+        let mut sections: BTreeMap<_, _> = vec![
+            (String::from("binds"), Self::binds_to_toml(&self.base_binds)),
+            (String::from("factors"), Table(factors)),
+        ].into_iter().collect();
+        if !processing.is_empty() {
+            sections.insert(String::from("processing"), Table(processing));
+        }
+        let mut options = BTreeMap::new();
+        if self.suppress_chord_components {
+            options.insert(String::from("suppress_chord_components"), Boolean(true));
+        }
+        if self.auto_assign_slots > 0 {
+            options.insert(String::from("auto_assign_slots"), Integer(i64::from(self.auto_assign_slots)));
+        }
+        if self.pause_mode == PauseMode::Release {
+            options.insert(String::from("pause_mode"), toml::Value::String(String::from("Release")));
+        }
+        if !options.is_empty() {
+            sections.insert(String::from("options"), Table(options));
+        }
+        let mut contexts = BTreeMap::new();
+        for (name, context) in self.contexts.iter() {
+            let mut context_table = BTreeMap::new();
+            context_table.insert(String::from("binds"), Self::binds_to_toml(&context.binds));
+            if context.consume {
+                context_table.insert(String::from("consume"), Boolean(true));
+            }
+            contexts.insert(name.clone(), Table(context_table));
+        }
+        if !contexts.is_empty() {
+            sections.insert(String::from("contexts"), Table(contexts));
+        }
+        // device ids change between runs, so only the slots are saved
+        let mut slots = BTreeMap::new();
+        for (scope, layer) in self.scoped_layers.iter() {
+            if let BindScope::Slot(slot) = scope {
+                if !layer.base_binds.is_empty() {
+                    slots.insert(slot.to_string(), Self::binds_to_toml(&layer.base_binds));
+                }
+            }
+        }
+        if !slots.is_empty() {
+            sections.insert(String::from("slots"), Table(slots));
+        }
+        Table(sections)
+    }
+
+    pub fn get_binds(&self) -> Vec<ControlBind<FireTarget, SwitchTarget, ValueTarget>> {
         use self::FireTrigger::*;
         use self::ValueTrigger::*;
         use self::MouseWheelDirection::*;
-        use toml::Value::Table;
-        use toml::Value::Float;
 
-        let mut binds = BTreeMap::new();
-        for (&trigger, data) in self.holdable_trigger_data.iter() {
-            for target in data.on_press.iter() {
-                binds.insert(target.to_string(), Holdable(trigger).to_toml());
+        let mut binds = Vec::new();
+        for (trigger, data) in self.holdable_trigger_data.iter() {
+            for &target in data.on_press.iter() {
+                binds.push(ControlBind::Fire(Holdable(trigger.clone()), target));
+            }
+            for &target in data.on_release.iter() {
+                binds.push(ControlBind::Fire(Release(trigger.clone()), target));
             }
-            for target in data.while_down.iter() {
-                binds.insert(target.to_string(), trigger.to_toml());
+            for &(threshold, target) in data.on_tap.iter() {
+                binds.push(ControlBind::Fire(Tap { trigger: trigger.clone(), threshold }, target));
+            }
+            for &(threshold, target) in data.on_long_press.iter() {
+                binds.push(ControlBind::Fire(LongPress { trigger: trigger.clone(), threshold }, target));
+            }
+            for &(delay, interval, target) in data.on_repeat.iter() {
+                binds.push(ControlBind::Fire(Repeat { trigger: trigger.clone(), delay, interval }, target));
+            }
+            for &target in data.while_down.iter() {
+                binds.push(ControlBind::Switch(trigger.clone(), target));
+            }
+            for &target in data.toggles.iter() {
+                binds.push(ControlBind::Toggle(trigger.clone(), target));
             }
         }
         for (&axis, mapping) in self.axis_mappings.iter() {
-            for target in mapping {
-                binds.insert(target.to_string(), toml::value::Value::Integer(axis as i64));
+            for &target in mapping {
+                binds.push(ControlBind::Value(Axis(axis), target));
+            }
+        }
+        for (&axis, mapping) in self.gamepad_axis_mappings.iter() {
+            for &target in mapping {
+                binds.push(ControlBind::Value(GamepadAxis(axis), target));
             }
         }
-        for target in self.mouse_wheel_mapping.on_up.iter() {
-            binds.insert(target.to_string(), MouseWheelTick(Up).to_toml());
+        for &target in self.mouse_motion_mapping.on_x.iter() {
+            binds.push(ControlBind::Value(MouseX, target));
         }
-        for target in self.mouse_wheel_mapping.on_down.iter() {
-            binds.insert(target.to_string(), MouseWheelTick(Down).to_toml());
+        for &target in self.mouse_motion_mapping.on_y.iter() {
+            binds.push(ControlBind::Value(MouseY, target));
         }
-        for target in self.mouse_wheel_mapping.on_change.iter() {
-            binds.insert(target.to_string(), MouseWheel.to_toml());
+        for &target in self.mouse_wheel_mapping.on_up.iter() {
+            binds.push(ControlBind::Fire(MouseWheelTick(Up), target));
         }
-
-        let mut factors = BTreeMap::new(); // TODO maybe just clone?
-        for (target, &factor) in self.value_factors.iter() {
-            factors.insert(target.to_string(), Float(factor));
+        for &target in self.mouse_wheel_mapping.on_down.iter() {
+            binds.push(ControlBind::Fire(MouseWheelTick(Down), target));
         }
-        Table(vec![
-            (String::from("binds"), Table(binds)),
-            (String::from("factors"), Table(factors)),
-        ].into_iter().collect())
+        for &target in self.mouse_wheel_mapping.on_change.iter() {
+            binds.push(ControlBind::Value(MouseWheel, target));
+        }
+        for (trigger, data) in self.vector_mappings.iter() {
+            for &target in data.targets.iter() {
+                binds.push(ControlBind::Vector(trigger.clone(), target));
+            }
+        }
+        for ((negative, positive, ramp_up, ramp_down), data) in self.composites.iter() {
+            for &target in data.targets.iter() {
+                binds.push(ControlBind::Value(Composite {
+                    negative: negative.clone(),
+                    positive: positive.clone(),
+                    ramp_up: *ramp_up,
+                    ramp_down: *ramp_down,
+                }, target));
+            }
+        }
+        for ((steps, timeout), data) in self.sequences.iter() {
+            for &target in data.on_complete.iter() {
+                binds.push(ControlBind::Fire(Sequence { steps: steps.clone(), timeout: *timeout }, target));
+            }
+        }
+        binds
     }
 
     pub fn set_factor(&mut self, target: ValueTarget, factor: f64) {
-        self.value_factors.insert(target, factor);
+        self.output.value_factors.insert(target, factor);
+    }
+
+    pub fn set_processing(&mut self, target: ValueTarget, processing: ValueProcessing) {
+        if processing == ValueProcessing::default() {
+            self.output.value_processing.remove(&target);
+        } else {
+            self.output.value_processing.insert(target, processing);
+        }
+    }
+
+    // while a chord is active, the plain binds of its components are not dispatched,
+    // so that e.g. LControl+S doesn't also trigger whatever S is bound to
+    pub fn set_suppress_chord_components(&mut self, suppress: bool) {
+        self.suppress_chord_components = suppress;
+        for layer in self.scoped_layers.values_mut() {
+            layer.suppress_chord_components = suppress;
+        }
     }
 
     pub fn add_bind(&mut self, bind: ControlBind<FireTarget, SwitchTarget, ValueTarget>) {
+        if self.base_binds.contains(&bind) || bind.is_empty_sequence() {
+            return;
+        }
+        self.base_binds.push(bind.clone());
+        if self.context_stack.is_empty() {
+            self.add_live_bind(bind);
+        } else {
+            self.refresh_binds();
+        }
+    }
+
+    pub fn remove_bind(&mut self, bind: ControlBind<FireTarget, SwitchTarget, ValueTarget>) {
+        self.base_binds.retain(|base_bind| *base_bind != bind);
+        if self.context_stack.is_empty() {
+            self.remove_live_bind(bind);
+        } else {
+            self.refresh_binds();
+        }
+    }
+
+    // replaces the context with the same name, if it is on the stack the new binds take effect immediately
+    pub fn set_context(&mut self, name: &str, context: Context<FireTarget, SwitchTarget, ValueTarget>) {
+        self.contexts.insert(String::from(name), context);
+        if self.context_stack.iter().any(|active| active == name) {
+            self.refresh_binds();
+        }
+    }
+
+    pub fn remove_context(&mut self, name: &str) -> Option<Context<FireTarget, SwitchTarget, ValueTarget>> {
+        let context = self.contexts.remove(name);
+        let stack_size = self.context_stack.len();
+        self.context_stack.retain(|active| active != name);
+        if self.context_stack.len() != stack_size {
+            self.refresh_binds();
+        }
+        context
+    }
+
+    // a context that isn't defined yet doesn't bind anything until it is
+    pub fn push_context(&mut self, name: &str) {
+        self.record(RecordedInput::PushContext(String::from(name)));
+        self.context_stack.push(String::from(name));
+        self.refresh_binds();
+    }
+
+    pub fn pop_context(&mut self) -> Option<String> {
+        self.record(RecordedInput::PopContext);
+        let name = self.context_stack.pop();
+        self.refresh_binds();
+        name
+    }
+
+    pub fn context_stack(&self) -> &[String] {
+        &self.context_stack
+    }
+
+    // the bind only reacts to the devices of the scope
+    pub fn add_scoped_bind(&mut self, scope: BindScope, bind: ControlBind<FireTarget, SwitchTarget, ValueTarget>) {
+        self.with_layer(scope, |layer| layer.add_bind(bind));
+    }
+
+    pub fn remove_scoped_bind(&mut self, scope: BindScope, bind: ControlBind<FireTarget, SwitchTarget, ValueTarget>) {
+        if self.scoped_layers.contains_key(&scope) {
+            self.with_layer(scope, |layer| layer.remove_bind(bind));
+        }
+    }
+
+    pub fn get_scoped_binds(&self, scope: BindScope) -> Vec<ControlBind<FireTarget, SwitchTarget, ValueTarget>> {
+        self.scoped_layers.get(&scope)
+            .map(|layer| layer.base_binds.clone())
+            .unwrap_or_default()
+    }
+
+    // a slot can have several devices, e.g. a keyboard and a mouse
+    pub fn assign_device(&mut self, device_id: DeviceId, slot: u32) {
+        if self.device_slots.get(&device_id) != Some(&slot) {
+            self.release_in_slot(device_id);
+        }
+        self.device_slots.insert(device_id, slot);
+    }
+
+    pub fn unassign_device(&mut self, device_id: DeviceId) -> Option<u32> {
+        self.release_in_slot(device_id);
+        self.device_slots.remove(&device_id)
+    }
+
+    pub fn device_slot(&self, device_id: DeviceId) -> Option<u32> {
+        self.device_slots.get(&device_id).cloned()
+    }
+
+    pub fn slot_devices(&self, slot: u32) -> impl Iterator<Item = DeviceId> + '_ {
+        self.device_slots.iter()
+            .filter(move |&(_, &device_slot)| device_slot == slot)
+            .map(|(&device_id, _)| device_id)
+    }
+
+    // 0 turns the automatic assignment off
+    pub fn set_auto_assign_slots(&mut self, slots: u32) {
+        self.auto_assign_slots = slots;
+    }
+
+    // runs `f` on the layer of the scope, with the output swapped in
+    fn with_layer<R, F>(&mut self, scope: BindScope, f: F) -> R
+    where F: FnOnce(&mut Self) -> R,
+    {
+        let time = self.time;
+        let suppress_chord_components = self.suppress_chord_components;
+        let layer = self.scoped_layers.entry(scope).or_insert_with(|| {
+            let mut layer = Controls::new();
+            layer.time = time;
+            layer.suppress_chord_components = suppress_chord_components;
+            layer
+        });
+        std::mem::swap(&mut self.output, &mut layer.output);
+        let result = f(layer);
+        std::mem::swap(&mut self.output, &mut layer.output);
+        result
+    }
+
+    // the slot the device leaves releases what the device held, like when it is removed
+    fn release_in_slot(&mut self, device_id: DeviceId) {
+        let scope = match self.device_slots.get(&device_id) {
+            Some(&slot) => BindScope::Slot(slot),
+            None => return,
+        };
+        if self.scoped_layers.contains_key(&scope) {
+            self.with_layer(scope, |layer| layer.on_device_removed(device_id));
+        }
+    }
+
+    fn device_scopes(&self, device_id: DeviceId) -> Vec<BindScope> {
+        let mut scopes = vec![BindScope::Device(device_id)];
+        if let Some(&slot) = self.device_slots.get(&device_id) {
+            scopes.push(BindScope::Slot(slot));
+        }
+        scopes.retain(|scope| self.scoped_layers.contains_key(scope));
+        scopes
+    }
+
+    // motion happens by accident too often, so only presses assign a device
+    fn auto_assign(&mut self, device_id: DeviceId, event: &InputEvent) {
+        use self::InputEvent::*;
+
+        let pressed = match *event {
+            Key { state, .. } | Button { state, .. } | GamepadButton { state, .. } => state == ElementState::Pressed,
+            _ => false,
+        };
+        if !pressed || self.device_slots.contains_key(&device_id) {
+            return;
+        }
+        let free_slot = (0..self.auto_assign_slots)
+            .find(|&slot| self.device_slots.values().all(|&device_slot| device_slot != slot));
+        if let Some(slot) = free_slot {
+            self.device_slots.insert(device_id, slot);
+            self.output.events.push_back(ControlEvent::DeviceAssigned { device_id, slot });
+        }
+    }
+
+    // the binds of the stack from the top down, then the base binds
+    fn effective_binds(&self) -> Vec<ControlBind<FireTarget, SwitchTarget, ValueTarget>> {
+        let mut binds: Vec<ControlBind<FireTarget, SwitchTarget, ValueTarget>> = Vec::new();
+        let mut consumed = Vec::new();
+        let layers = self.context_stack.iter().rev()
+            .filter_map(|name| self.contexts.get(name))
+            .map(|context| (&context.binds, context.consume))
+            .chain(iter::once((&self.base_binds, false)));
+        for (layer_binds, consume) in layers {
+            for bind in layer_binds {
+                let is_consumed = bind.inputs().iter().any(|input| consumed.contains(input));
+                if !is_consumed && !binds.contains(bind) {
+                    binds.push(bind.clone());
+                }
+            }
+            if consume {
+                consumed.extend(layer_binds.iter().flat_map(ControlBind::inputs));
+            }
+        }
+        binds
+    }
+
+    // only applies the difference, so that the binds that stay keep their state,
+    // the removed switch binds release their targets
+    fn refresh_binds(&mut self) {
+        let effective = self.effective_binds();
+        let live = self.get_binds();
+        for bind in live.iter().filter(|&bind| !effective.contains(bind)) {
+            self.remove_live_bind(bind.clone());
+        }
+        for bind in effective.into_iter().filter(|bind| !live.contains(bind)) {
+            self.add_live_bind(bind);
+        }
+    }
+
+    fn add_live_bind(&mut self, bind: ControlBind<FireTarget, SwitchTarget, ValueTarget>) {
         match bind {
             ControlBind::Fire(trigger, target) => self.add_fire_bind(trigger, target),
             ControlBind::Switch(trigger, target) => self.add_switch_bind(trigger, target),
+            ControlBind::Toggle(trigger, target) => {
+                self.holdable_trigger_data.entry(trigger)
+                    .or_insert_with(HoldableTriggerData::new)
+                    .toggles.insert(target);
+            },
             ControlBind::Value(trigger, target) => self.add_value_bind(trigger, target),
+            ControlBind::Vector(trigger, target) => {
+                // the key vectors read the state of their triggers from the entries
+                if let VectorTrigger::Keys { ref up, ref down, ref left, ref right } = trigger {
+                    for direction in [up, down, left, right].iter() {
+                        self.holdable_trigger_data.entry((*direction).clone())
+                            .or_insert_with(HoldableTriggerData::new);
+                    }
+                }
+                self.vector_mappings.entry(trigger)
+                    .or_insert_with(VectorData::new)
+                    .targets.insert(target);
+            },
         };
     }
 
-    pub fn remove_bind(&mut self, bind: ControlBind<FireTarget, SwitchTarget, ValueTarget>) {
+    fn remove_live_bind(&mut self, bind: ControlBind<FireTarget, SwitchTarget, ValueTarget>) {
         match bind {
             ControlBind::Fire(trigger, target) => self.remove_fire_bind(trigger, target),
             ControlBind::Switch(trigger, target) => self.remove_switch_bind(trigger, target),
+            ControlBind::Toggle(trigger, target) => self.remove_toggle_bind(trigger, target),
             ControlBind::Value(trigger, target) => self.remove_value_bind(trigger, target),
+            ControlBind::Vector(trigger, target) => {
+                self.vector_mappings.get_mut(&trigger).map(|data| data.targets.remove(&target));
+            },
         };
     }
 
     pub fn pause(&mut self) {
-        self.paused = true;
+        self.record(RecordedInput::Pause);
+        if self.pause_mode == PauseMode::Release && !self.output.paused {
+            self.forget_held_state();
+        }
+        self.output.paused = true;
+    }
+
+    pub fn set_pause_mode(&mut self, mode: PauseMode) {
+        self.pause_mode = mode;
+    }
+
+    // forgets every held trigger without dispatching their releases, the switches they held
+    // and the toggled ones become inactive, e.g. for when the window loses focus and the releases never arrive
+    pub fn reset_held_state(&mut self) {
+        self.record(RecordedInput::ResetHeldState);
+        self.forget_held_state();
+    }
+
+    fn forget_held_state(&mut self) {
+        for data in self.holdable_trigger_data.values_mut() {
+            if data.overall_counter > 0 && !data.suppressed {
+                for &switch_target in data.while_down.iter() {
+                    self.output.decrease_switch_target_counter(switch_target);
+                }
+            }
+            for switch_target in data.toggled_on.drain() {
+                self.output.decrease_switch_target_counter(switch_target);
+            }
+            data.device_counters.clear();
+            data.overall_counter = 0;
+            data.suppressed = false;
+        }
+        self.reached_axis_thresholds.clear();
+        for data in self.sequences.values_mut() {
+            data.progress = 0;
+        }
+        // nothing is held anymore, so the composites skip their ramps
+        for data in self.composites.values_mut() {
+            if data.value != 0.0 {
+                data.value = 0.0;
+                for &target in data.targets.iter() {
+                    data.stale |= !self.output.value(target, 0.0);
+                }
+            }
+        }
+        self.update_key_vectors();
+        for layer in self.scoped_layers.values_mut() {
+            std::mem::swap(&mut self.output, &mut layer.output);
+            layer.forget_held_state();
+            std::mem::swap(&mut self.output, &mut layer.output);
+        }
     }
 
     pub fn resume(&mut self) {
-        if self.paused {
-            for (&target, counter) in self.switch_counters.iter_mut() {
-                if counter.counter > 0 && counter.announced_state == SwitchState::Inactive {
-                    counter.announced_state = SwitchState::Active;
-                    self.events.push_back(ControlEvent::Switch {
-                        target,
-                        state: SwitchState::Active,
-                    });
-                } else if counter.counter == 0 && counter.announced_state == SwitchState::Active {
-                    counter.announced_state = SwitchState::Inactive;
-                    self.events.push_back(ControlEvent::Switch {
-                        target,
-                        state: SwitchState::Inactive,
-                    });
+        self.record(RecordedInput::Resume);
+        if self.output.paused {
+            self.output.paused = false;
+            self.output.announce_switch_states();
+            self.announce_stale_values();
+        }
+    }
+
+    // announces the composite and key vector values that changed while they couldn't be
+    fn announce_stale_values(&mut self) {
+        for data in self.composites.values_mut() {
+            if std::mem::replace(&mut data.stale, false) {
+                for &target in data.targets.iter() {
+                    data.stale |= !self.output.value(target, data.value);
+                }
+            }
+        }
+        for data in self.vector_mappings.values_mut() {
+            if std::mem::replace(&mut data.stale, false) {
+                for &target in data.targets.iter() {
+                    data.stale |= !self.output.vector(target, data.value);
+                }
+            }
+        }
+        for layer in self.scoped_layers.values_mut() {
+            std::mem::swap(&mut self.output, &mut layer.output);
+            layer.announce_stale_values();
+            std::mem::swap(&mut self.output, &mut layer.output);
+        }
+    }
+
+    // disabled targets emit no events, disabled switches are announced as inactive
+    // until they are enabled again
+    pub fn disable_target(&mut self, target: Target<FireTarget, SwitchTarget, ValueTarget>) {
+        self.disable_targets(iter::once(target));
+    }
+
+    pub fn disable_targets<I>(&mut self, targets: I)
+    where I: IntoIterator<Item = Target<FireTarget, SwitchTarget, ValueTarget>>,
+    {
+        self.set_targets_enabled(targets, false);
+    }
+
+    pub fn enable_target(&mut self, target: Target<FireTarget, SwitchTarget, ValueTarget>) {
+        self.enable_targets(iter::once(target));
+    }
+
+    pub fn enable_targets<I>(&mut self, targets: I)
+    where I: IntoIterator<Item = Target<FireTarget, SwitchTarget, ValueTarget>>,
+    {
+        self.set_targets_enabled(targets, true);
+    }
+
+    pub fn is_target_enabled(&self, target: Target<FireTarget, SwitchTarget, ValueTarget>) -> bool {
+        match target {
+            Target::Fire(target) => !self.output.disabled_fire_targets.contains(&target),
+            Target::Switch(target) => !self.output.disabled_switch_targets.contains(&target),
+            Target::Value(target) => !self.output.disabled_value_targets.contains(&target),
+        }
+    }
+
+    fn set_targets_enabled<I>(&mut self, targets: I, enabled: bool)
+    where I: IntoIterator<Item = Target<FireTarget, SwitchTarget, ValueTarget>>,
+    {
+        fn update<T: Eq + Hash>(set: &mut HashSet<T>, target: T, enabled: bool) {
+            if enabled {
+                set.remove(&target);
+            } else {
+                set.insert(target);
+            }
+        }
+
+        for target in targets {
+            let name = match target {
+                Target::Fire(target) => target.to_string(),
+                Target::Switch(target) => target.to_string(),
+                Target::Value(target) => target.to_string(),
+            };
+            self.record(if enabled {
+                RecordedInput::EnableTarget(name)
+            } else {
+                RecordedInput::DisableTarget(name)
+            });
+            match target {
+                Target::Fire(target) => update(&mut self.output.disabled_fire_targets, target, enabled),
+                Target::Switch(target) => update(&mut self.output.disabled_switch_targets, target, enabled),
+                Target::Value(target) => update(&mut self.output.disabled_value_targets, target, enabled),
+            }
+        }
+        if !self.output.paused {
+            self.output.announce_switch_states();
+            self.announce_stale_values();
+        }
+    }
+
+    pub fn is_active(&self, target: SwitchTarget) -> bool {
+        self.output.switch_counters.get(&target)
+            .is_some_and(|counter| counter.announced_state == SwitchState::Active)
+    }
+
+    pub fn active_switches(&self) -> impl Iterator<Item = SwitchTarget> + '_ {
+        self.output.switch_counters.iter()
+            .filter(|(_, counter)| counter.announced_state == SwitchState::Active)
+            .map(|(&target, _)| target)
+    }
+
+    // sum of the mouse motion and wheel values emitted for the target since the last call to end_frame,
+    // axes and keys report positions, so they aren't summed up
+    pub fn accumulated_value(&self, target: ValueTarget) -> f64 {
+        *self.output.accumulated_values.get(&target).unwrap_or(&0.0)
+    }
+
+    pub fn accumulated_vector(&self, target: ValueTarget) -> (f64, f64) {
+        *self.output.accumulated_vectors.get(&target).unwrap_or(&(0.0, 0.0))
+    }
+
+    pub fn end_frame(&mut self) {
+        self.output.accumulated_values.clear();
+        self.output.accumulated_vectors.clear();
+    }
+
+    // advances the clock of time based triggers like sequences, long presses and repeats
+    pub fn tick(&mut self, dt: Duration) {
+        self.record(RecordedInput::Tick(dt));
+        let previous_time = self.time;
+        self.time += dt;
+        for data in self.holdable_trigger_data.values() {
+            if data.overall_counter == 0 || data.suppressed {
+                continue;
+            }
+            let held_before = previous_time - data.pressed_at;
+            let held_now = self.time - data.pressed_at;
+            for &(threshold, target) in data.on_long_press.iter() {
+                if held_before < threshold && threshold <= held_now {
+                    self.output.fire(target);
+                }
+            }
+            for &(delay, interval, target) in data.on_repeat.iter() {
+                let repetitions = |held: Duration| if held < delay {
+                    0
+                } else {
+                    1 + (held - delay).as_nanos() / interval.as_nanos().max(1)
+                };
+                for _ in repetitions(held_before)..repetitions(held_now) {
+                    self.output.fire(target);
                 }
             }
         }
-        self.paused = false;
+        self.update_composites(dt);
+        for layer in self.scoped_layers.values_mut() {
+            std::mem::swap(&mut self.output, &mut layer.output);
+            layer.tick(dt);
+            std::mem::swap(&mut self.output, &mut layer.output);
+        }
     }
 
     pub fn process(
         &mut self,
         device_id: DeviceId,
-        device_event: DeviceEvent,
+        event: InputEvent,
     ) {
-        match device_event {
-            DeviceEvent::MouseWheel { delta } => self.on_mouse_wheel(device_id, delta),
-            DeviceEvent::Motion { axis, value } => self.on_motion(device_id, axis, value),
-            DeviceEvent::MouseMotion { delta } => self.on_mouse_motion(device_id, delta),
-            DeviceEvent::Button { button, state } => self.on_button(device_id, button, state),
-            DeviceEvent::Key(input) => self.on_keyboard_input(device_id, input),
-            DeviceEvent::Removed => self.on_device_removed(device_id),
-            _ => (),
+        if let Some(ref mut capture) = self.capture {
+            if capture.on_event(&event) {
+                return;
+            }
+        }
+        // after the capture, so that a replay doesn't dispatch what it swallowed
+        self.record(RecordedInput::Event { time: self.time, device_id, event });
+        self.auto_assign(device_id, &event);
+        self.dispatch(device_id, event);
+        for scope in self.device_scopes(device_id) {
+            self.with_layer(scope, |layer| layer.dispatch(device_id, event));
+        }
+        if event == InputEvent::Removed {
+            self.output.events.push_back(ControlEvent::DeviceDisconnected(device_id));
+            if let Some(slot) = self.device_slots.remove(&device_id) {
+                self.output.events.push_back(ControlEvent::DeviceUnassigned { device_id, slot });
+            }
+        }
+    }
+
+    fn dispatch(&mut self, device_id: DeviceId, event: InputEvent) {
+        match event {
+            InputEvent::MouseWheel { delta } => self.on_mouse_wheel(device_id, delta),
+            InputEvent::Motion { axis, value } => self.on_motion(device_id, axis, value),
+            InputEvent::MouseMotion { delta } => self.on_mouse_motion(device_id, delta),
+            InputEvent::Button { button, state } => self.on_button(device_id, button, state),
+            InputEvent::Key { scan_code, key_code, state } =>
+                self.on_keyboard_input(device_id, scan_code, key_code, state),
+            InputEvent::GamepadButton { button, state } =>
+                self.handle_holdable_trigger(HoldableTrigger::GamepadButton(button), device_id, state),
+            InputEvent::GamepadAxis { axis, value } => self.on_gamepad_axis(device_id, axis, value),
+            InputEvent::Removed => self.on_device_removed(device_id),
+        }
+        self.advance_sequences();
+    }
+
+    // until a trigger is captured, presses and axis movements are collected instead of dispatched
+    pub fn start_capture(&mut self, options: CaptureOptions) {
+        self.capture = Some(Capture::new(options));
+    }
+
+    pub fn cancel_capture(&mut self) {
+        self.capture = None;
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.capture.as_ref().is_some_and(|capture| capture.result.is_none())
+    }
+
+    // ends the capture if a trigger was captured
+    pub fn take_captured_trigger(&mut self) -> Option<CapturedTrigger> {
+        let trigger = self.capture.as_mut()?.result.take()?;
+        self.capture = None;
+        Some(trigger)
+    }
+
+    // records every dispatched input event, tick, pause, resume, reset, context push and pop
+    // and target toggle until the recording is stopped
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Recording::new());
+    }
+
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        self.recording.take()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    fn record(&mut self, input: RecordedInput) {
+        if let Some(ref mut recording) = self.recording {
+            recording.push(input);
         }
     }
 
     pub fn get_events(&mut self, events: &mut VecDeque<ControlEvent<FireTarget, SwitchTarget, ValueTarget>>) {
         events.clear();
-        std::mem::swap(&mut self.events, events);
+        std::mem::swap(&mut self.output.events, events);
     }
 
     fn add_fire_bind(&mut self, trigger: FireTrigger, target: FireTarget) {
@@ -330,13 +1381,43 @@ where FireTarget: Copy + Eq + Hash + FromStr + ToString,
                     .or_insert_with(HoldableTriggerData::new)
                     .on_press.insert(target);
             },
+            Release(holdable_trigger) => {
+                self.holdable_trigger_data.entry(holdable_trigger)
+                    .or_insert_with(HoldableTriggerData::new)
+                    .on_release.insert(target);
+            },
+            Tap { trigger, threshold } => {
+                self.holdable_trigger_data.entry(trigger)
+                    .or_insert_with(HoldableTriggerData::new)
+                    .on_tap.insert((threshold, target));
+            },
+            LongPress { trigger, threshold } => {
+                self.holdable_trigger_data.entry(trigger)
+                    .or_insert_with(HoldableTriggerData::new)
+                    .on_long_press.insert((threshold, target));
+            },
+            Repeat { trigger, delay, interval } => {
+                self.holdable_trigger_data.entry(trigger)
+                    .or_insert_with(HoldableTriggerData::new)
+                    .on_repeat.insert((delay, interval, target));
+            },
             MouseWheelTick(direction) => {
                 let mapping = &mut self.mouse_wheel_mapping;
                 match direction {
                     Up => mapping.on_up.insert(target),
                     Down => mapping.on_down.insert(target),
                 };
-            }
+            },
+            Sequence { steps, timeout } => {
+                // chord steps are only detected if the chord has an entry
+                for step in steps.iter() {
+                    self.holdable_trigger_data.entry(step.clone())
+                        .or_insert_with(HoldableTriggerData::new);
+                }
+                self.sequences.entry((steps, timeout))
+                    .or_insert_with(SequenceData::new)
+                    .on_complete.insert(target);
+            },
         };
     }
 
@@ -346,12 +1427,7 @@ where FireTarget: Copy + Eq + Hash + FromStr + ToString,
         let bind_is_new = data.while_down.insert(target);
         let trigger_is_active = data.overall_counter > 0;
         if bind_is_new && trigger_is_active {
-            Self::increase_switch_target_counter(
-                target,
-                &mut self.switch_counters,
-                &mut self.events,
-                self.paused,
-            );
+            self.output.increase_switch_target_counter(target);
         }
     }
 
@@ -360,16 +1436,29 @@ where FireTarget: Copy + Eq + Hash + FromStr + ToString,
 
         match trigger {
             MouseX => {
-                // TODO
+                self.mouse_motion_mapping.on_x.insert(target);
             },
             MouseY => {
-                // TODO
+                self.mouse_motion_mapping.on_y.insert(target);
             },
             MouseWheel => {
                 self.mouse_wheel_mapping.on_change.insert(target);
             },
             Axis(axis) => {
-                self.axis_mappings.entry(axis).or_insert_with(Default::default).insert(target);
+                self.axis_mappings.entry(axis).or_default().insert(target);
+            },
+            GamepadAxis(axis) => {
+                self.gamepad_axis_mappings.entry(axis).or_default().insert(target);
+            },
+            Composite { negative, positive, ramp_up, ramp_down } => {
+                // the composites read the state of their triggers from the entries
+                for trigger in [&negative, &positive].iter() {
+                    self.holdable_trigger_data.entry((*trigger).clone())
+                        .or_insert_with(HoldableTriggerData::new);
+                }
+                self.composites.entry((negative, positive, ramp_up, ramp_down))
+                    .or_insert_with(CompositeData::new)
+                    .targets.insert(target);
             },
         };
     }
@@ -383,8 +1472,28 @@ where FireTarget: Copy + Eq + Hash + FromStr + ToString,
                 self.holdable_trigger_data.get_mut(&holdable_trigger)
                     .map(|binding| binding.on_press.remove(&target));
             },
+            Release(holdable_trigger) => {
+                self.holdable_trigger_data.get_mut(&holdable_trigger)
+                    .map(|binding| binding.on_release.remove(&target));
+            },
+            Tap { trigger, threshold } => {
+                self.holdable_trigger_data.get_mut(&trigger)
+                    .map(|binding| binding.on_tap.remove(&(threshold, target)));
+            },
+            LongPress { trigger, threshold } => {
+                self.holdable_trigger_data.get_mut(&trigger)
+                    .map(|binding| binding.on_long_press.remove(&(threshold, target)));
+            },
+            Repeat { trigger, delay, interval } => {
+                self.holdable_trigger_data.get_mut(&trigger)
+                    .map(|binding| binding.on_repeat.remove(&(delay, interval, target)));
+            },
             MouseWheelTick(Up) => { self.mouse_wheel_mapping.on_up.remove(&target); },
             MouseWheelTick(Down) => { self.mouse_wheel_mapping.on_down.remove(&target); },
+            Sequence { steps, timeout } => {
+                self.sequences.get_mut(&(steps, timeout))
+                    .map(|data| data.on_complete.remove(&target));
+            },
         }
     }
 
@@ -393,12 +1502,16 @@ where FireTarget: Copy + Eq + Hash + FromStr + ToString,
             let bind_existed = data.while_down.remove(&target);
             let trigger_is_active = data.overall_counter > 0;
             if bind_existed && trigger_is_active {
-                Self::decrease_switch_target_counter(
-                    target,
-                    &mut self.switch_counters,
-                    &mut self.events,
-                    self.paused,
-                );
+                self.output.decrease_switch_target_counter(target);
+            }
+        }
+    }
+
+    fn remove_toggle_bind(&mut self, trigger: HoldableTrigger, target: SwitchTarget) {
+        if let Some(data) = self.holdable_trigger_data.get_mut(&trigger) {
+            data.toggles.remove(&target);
+            if data.toggled_on.remove(&target) {
+                self.output.decrease_switch_target_counter(target);
             }
         }
     }
@@ -408,10 +1521,10 @@ where FireTarget: Copy + Eq + Hash + FromStr + ToString,
 
         match trigger {
             MouseX => {
-                // TODO
+                self.mouse_motion_mapping.on_x.remove(&target);
             },
             MouseY => {
-                // TODO
+                self.mouse_motion_mapping.on_y.remove(&target);
             },
             MouseWheel => {
                 self.mouse_wheel_mapping.on_change.remove(&target);
@@ -419,90 +1532,152 @@ where FireTarget: Copy + Eq + Hash + FromStr + ToString,
             Axis(axis) => {
                 self.axis_mappings.get_mut(&axis).map(|binding| binding.remove(&target));
             },
+            GamepadAxis(axis) => {
+                self.gamepad_axis_mappings.get_mut(&axis).map(|binding| binding.remove(&target));
+            },
+            Composite { negative, positive, ramp_up, ramp_down } => {
+                self.composites.get_mut(&(negative, positive, ramp_up, ramp_down))
+                    .map(|data| data.targets.remove(&target));
+            },
         };
     }
 
-    fn on_motion(&mut self, _device_id: DeviceId, axis: u32, value: f64) {
-        use self::ControlEvent::*;
-
+    fn on_motion(&mut self, device_id: DeviceId, axis: u32, value: f64) {
         if let Some(mapping) = self.axis_mappings.get(&axis) {
             for &target in mapping {
-                if value != 0.0 && !self.paused {
-                    let factor = self.value_factors.get(&target).unwrap_or(&1.0);
-                    self.events.push_back(Value {
-                        target,
-                        value: value * factor * target.base_factor(),
-                    });
+                if value != 0.0 {
+                    self.output.value(target, value);
                 }
             }
         }
+        for (trigger, data) in self.vector_mappings.iter_mut() {
+            if let VectorTrigger::Axes(x_axis, y_axis) = *trigger {
+                if x_axis == axis {
+                    data.value.0 = value;
+                } else if y_axis == axis {
+                    data.value.1 = value;
+                } else {
+                    continue;
+                }
+                for &target in data.targets.iter() {
+                    self.output.vector(target, data.value);
+                }
+            }
+        }
+        self.update_axis_thresholds(device_id, ThresholdAxis::Axis(axis), value);
     }
 
-    fn on_mouse_motion(&mut self, _device_id: DeviceId, _delta: (f64, f64)) {
-        // TODO
-        /*use self::ControlEvent::*;
+    fn update_axis_thresholds(&mut self, device_id: DeviceId, axis: ThresholdAxis, value: f64) {
+        use self::ElementState::*;
 
-        if let Some(mapping) = self.axis_mappings.get(&axis) {
+        for threshold in self.bound_axis_thresholds(axis) {
+            let was_reached = self.reached_axis_thresholds.contains(&(threshold, device_id));
+            let is_reached = threshold.is_reached(value, was_reached);
+            if is_reached && !was_reached {
+                self.reached_axis_thresholds.insert((threshold, device_id));
+                self.handle_holdable_trigger(HoldableTrigger::AxisThreshold(threshold), device_id, Pressed);
+            } else if !is_reached && was_reached {
+                self.reached_axis_thresholds.remove(&(threshold, device_id));
+                self.handle_holdable_trigger(HoldableTrigger::AxisThreshold(threshold), device_id, Released);
+            }
+        }
+    }
+
+    // including the ones that are only bound as part of a chord
+    fn bound_axis_thresholds(&self, axis: ThresholdAxis) -> HashSet<AxisThreshold> {
+        let mut thresholds = HashSet::new();
+        for trigger in self.holdable_trigger_data.keys() {
+            match trigger {
+                HoldableTrigger::AxisThreshold(threshold) => { thresholds.insert(*threshold); },
+                HoldableTrigger::Chord(components) => for component in components {
+                    if let HoldableTrigger::AxisThreshold(threshold) = component {
+                        thresholds.insert(*threshold);
+                    }
+                },
+                _ => (),
+            }
+        }
+        thresholds.retain(|threshold| threshold.axis == axis);
+        thresholds
+    }
+
+    fn on_gamepad_axis(&mut self, device_id: DeviceId, axis: GamepadAxis, value: f64) {
+        if let Some(mapping) = self.gamepad_axis_mappings.get(&axis) {
             for &target in mapping {
-                if value != 0.0 {
-                    let factor = self.value_factors.get(&target).unwrap_or(&1.0);
-                    self.events.push_back(Value {
-                        target,
-                        value: value * factor * target.base_factor(),
-                    });
+                self.output.value(target, value);
+            }
+        }
+        for (trigger, data) in self.vector_mappings.iter_mut() {
+            if let VectorTrigger::GamepadAxes(x_axis, y_axis) = *trigger {
+                if x_axis == axis {
+                    data.value.0 = value;
+                } else if y_axis == axis {
+                    data.value.1 = value;
+                } else {
+                    continue;
+                }
+                // sticks report a square, but should cover a circle
+                let length = data.value.0.hypot(data.value.1).max(1.0);
+                for &target in data.targets.iter() {
+                    self.output.vector(target, (data.value.0 / length, data.value.1 / length));
                 }
             }
-        }*/
+        }
+        self.update_axis_thresholds(device_id, ThresholdAxis::GamepadAxis(axis), value);
+    }
+
+    fn on_mouse_motion(&mut self, _device_id: DeviceId, delta: (f64, f64)) {
+        let (dx, dy) = delta;
+        let mapping = &self.mouse_motion_mapping;
+        for &(targets, value) in [(&mapping.on_x, dx), (&mapping.on_y, dy)].iter() {
+            if value == 0.0 {
+                continue;
+            }
+            for &target in targets.iter() {
+                self.output.delta(target, value);
+            }
+        }
+        if let Some(data) = self.vector_mappings.get(&VectorTrigger::Mouse) {
+            for &target in data.targets.iter() {
+                self.output.vector_delta(target, delta);
+            }
+        }
     }
 
-    fn on_keyboard_input(&mut self, device_id: DeviceId, input: KeyboardInput) {
+    fn on_keyboard_input(&mut self, device_id: DeviceId, scan_code: u32,
+                         key_code: Option<VirtualKeyCode>, state: ElementState) {
         use self::HoldableTrigger::*;
-        if let Some(key_code) = input.virtual_keycode {
-            self.handle_holdable_trigger(KeyCode(key_code), device_id, input.state);
+        if let Some(key_code) = key_code {
+            self.handle_holdable_trigger(KeyCode(key_code), device_id, state);
         }
-        self.handle_holdable_trigger(ScanCode(input.scancode), device_id, input.state);
+        self.handle_holdable_trigger(ScanCode(scan_code), device_id, state);
     }
 
-    fn on_button(&mut self, device_id: DeviceId, button_id: ButtonId,
+    fn on_button(&mut self, device_id: DeviceId, button_id: u32,
                  state: ElementState) {
         self.handle_holdable_trigger(HoldableTrigger::Button(button_id), device_id, state);
     }
 
-    fn on_mouse_wheel(&mut self, _device_id: DeviceId, delta: MouseScrollDelta) {
-        use self::MouseScrollDelta::*;
-        use self::ControlEvent::*;
-
-        let value = match delta { // TODO also handle x and PixelDelta?
-            LineDelta(_x, y) => y as f64,
-            PixelDelta(_) => return,
-        };
-
-        if !self.paused {
-            if value < 0.0 {
-                for &fire_target in self.mouse_wheel_mapping.on_up.iter() {
-                    self.events.push_back(Fire(fire_target));
-                }
-            } else if value > 0.0 {
-                for &fire_target in self.mouse_wheel_mapping.on_down.iter() {
-                    self.events.push_back(Fire(fire_target));
-                }
+    fn on_mouse_wheel(&mut self, _device_id: DeviceId, value: f64) {
+        if value < 0.0 {
+            for &fire_target in self.mouse_wheel_mapping.on_up.iter() {
+                self.output.fire(fire_target);
             }
-            for &target in self.mouse_wheel_mapping.on_change.iter() {
-                let factor = self.value_factors.get(&target).unwrap_or(&1.0);
-                self.events.push_back(Value {
-                    target,
-                    value: value * factor * target.base_factor(),
-                });
+        } else if value > 0.0 {
+            for &fire_target in self.mouse_wheel_mapping.on_down.iter() {
+                self.output.fire(fire_target);
             }
         }
+        for &target in self.mouse_wheel_mapping.on_change.iter() {
+            self.output.delta(target, value);
+        }
     }
 
     fn handle_holdable_trigger(&mut self, trigger: HoldableTrigger, device_id: DeviceId,
                                state: ElementState) {
         use self::ElementState::*;
-        use self::ControlEvent::*;
 
-        let data = self.holdable_trigger_data.entry(trigger)
+        let data = self.holdable_trigger_data.entry(trigger.clone())
             .or_insert_with(HoldableTriggerData::new);
         let device_counter = data.device_counters.entry(device_id).or_insert(0);
         let overall_counter = &mut data.overall_counter;
@@ -517,6 +1692,7 @@ where FireTarget: Copy + Eq + Hash + FromStr + ToString,
             Released => {
                 if *device_counter == 0 {
                     // apparently the trigger was active before controls were initialized
+                    // or before the held state was reset
                     return;
                 }
                 *device_counter -= 1;
@@ -527,179 +1703,230 @@ where FireTarget: Copy + Eq + Hash + FromStr + ToString,
             },
         }
 
-        if let Some(data) = self.holdable_trigger_data.get_mut(&trigger) {
-            if state == Pressed && !self.paused {
-                for &fire_target in data.on_press.iter() {
-                    self.events.push_back(Fire(fire_target));
-                }
+        let chords = self.changed_chords(&trigger);
+        if self.suppress_chord_components && state == Pressed {
+            for chord in chords.iter() {
+                self.suppress_components(chord, &trigger);
             }
-            for &switch_target in data.while_down.iter() {
+        }
+        if let Some(data) = self.holdable_trigger_data.get_mut(&trigger) {
+            let suppressed = match state {
+                Pressed => {
+                    data.suppressed = self.suppress_chord_components && !chords.is_empty();
+                    data.suppressed
+                },
+                Released => std::mem::replace(&mut data.suppressed, false),
+            };
+            if !suppressed {
                 match state {
-                    Pressed => Self::increase_switch_target_counter(
-                        switch_target,
-                        &mut self.switch_counters,
-                        &mut self.events,
-                        self.paused,
-                    ),
-                    Released => Self::decrease_switch_target_counter(
-                        switch_target,
-                        &mut self.switch_counters,
-                        &mut self.events,
-                        self.paused,
-                    ),
+                    Pressed => {
+                        data.pressed_at = self.time;
+                        for &fire_target in data.on_press.iter() {
+                            self.output.fire(fire_target);
+                        }
+                        for &(_, _, fire_target) in data.on_repeat.iter() {
+                            self.output.fire(fire_target);
+                        }
+                        for &switch_target in data.toggles.iter() {
+                            if data.toggled_on.remove(&switch_target) {
+                                self.output.decrease_switch_target_counter(switch_target);
+                            } else {
+                                data.toggled_on.insert(switch_target);
+                                self.output.increase_switch_target_counter(switch_target);
+                            }
+                        }
+                    },
+                    Released => {
+                        for &fire_target in data.on_release.iter() {
+                            self.output.fire(fire_target);
+                        }
+                        let held = self.time - data.pressed_at;
+                        for &(threshold, fire_target) in data.on_tap.iter() {
+                            if held < threshold {
+                                self.output.fire(fire_target);
+                            }
+                        }
+                    },
+                }
+                for &switch_target in data.while_down.iter() {
+                    match state {
+                        Pressed => self.output.increase_switch_target_counter(switch_target),
+                        Released => self.output.decrease_switch_target_counter(switch_target),
+                    }
                 }
             }
         }
+
+        if state == Pressed {
+            self.pressed_triggers.push(trigger.clone());
+        }
+        self.update_composites(Duration::from_secs(0));
+        self.update_key_vectors();
+
+        for chord in chords {
+            // a chord is held by the device that completed it
+            let device_id = match state {
+                Pressed => device_id,
+                Released => match self.holdable_trigger_data[&chord].device_counters.iter()
+                    .find(|&(_, &counter)| counter > 0)
+                {
+                    Some((&device_id, _)) => device_id,
+                    None => continue,
+                },
+            };
+            self.handle_holdable_trigger(chord, device_id, state);
+        }
     }
 
-    fn on_device_removed(&mut self, device_id: DeviceId) {
-        for data in self.holdable_trigger_data.values_mut() {
-            if let Some(device_counter) = data.device_counters.get_mut(&device_id) {
-                data.overall_counter -= *device_counter;
-                *device_counter = 0;
+    // moves every composite value towards the direction that is currently held
+    fn update_composites(&mut self, dt: Duration) {
+        let holdable_trigger_data = &self.holdable_trigger_data;
+        let is_active = |trigger: &HoldableTrigger| holdable_trigger_data.get(trigger)
+            .is_some_and(|data| data.overall_counter > 0);
+        for ((negative, positive, ramp_up, ramp_down), data) in self.composites.iter_mut() {
+            let goal = match (is_active(negative), is_active(positive)) {
+                (true, false) => -1.0,
+                (false, true) => 1.0,
+                _ => 0.0,
+            };
+            if data.value == goal {
+                continue;
+            }
+            let ramp = if goal * data.value >= 0.0 && goal.abs() > data.value.abs() {
+                ramp_up
+            } else {
+                ramp_down
+            };
+            let step = if *ramp == Duration::from_secs(0) {
+                2.0
+            } else {
+                dt.as_secs_f64() / ramp.as_secs_f64()
+            };
+            let value = if goal > data.value {
+                (data.value + step).min(goal)
+            } else {
+                (data.value - step).max(goal)
+            };
+            if value == data.value {
+                continue;
+            }
+            data.value = value;
+            for &target in data.targets.iter() {
+                data.stale |= !self.output.value(target, value);
             }
         }
     }
 
-    fn increase_switch_target_counter(
-        target: SwitchTarget,
-        switch_counter: &mut HashMap<SwitchTarget, SwitchCounter>,
-        events: &mut VecDeque<ControlEvent<FireTarget, SwitchTarget, ValueTarget>>,
-        paused: bool,
-    ) {
-        let counter = switch_counter.entry(target).or_insert(SwitchCounter {
-            counter: 0,
-            announced_state: SwitchState::Inactive,
-        });
-        if counter.counter == 0 && !paused {
-            counter.announced_state = SwitchState::Active;
-            events.push_back(ControlEvent::Switch {
-                target,
-                state: SwitchState::Active,
-            });
+    fn update_key_vectors(&mut self) {
+        let holdable_trigger_data = &self.holdable_trigger_data;
+        let is_active = |trigger: &HoldableTrigger| holdable_trigger_data.get(trigger)
+            .is_some_and(|data| data.overall_counter > 0);
+        let direction = |negative, positive| match (is_active(negative), is_active(positive)) {
+            (true, false) => -1.0,
+            (false, true) => 1.0,
+            _ => 0.0,
+        };
+        for (trigger, data) in self.vector_mappings.iter_mut() {
+            if let VectorTrigger::Keys { up, down, left, right } = trigger {
+                let (x, y): (f64, f64) = (direction(left, right), direction(down, up));
+                let length = x.hypot(y).max(1.0);
+                let value = (x / length, y / length);
+                if value == data.value {
+                    continue;
+                }
+                data.value = value;
+                for &target in data.targets.iter() {
+                    data.stale |= !self.output.vector(target, value);
+                }
+            }
         }
-        counter.counter += 1;
     }
 
-    fn decrease_switch_target_counter(
-        target: SwitchTarget,
-        switch_counter: &mut HashMap<SwitchTarget, SwitchCounter>,
-        events: &mut VecDeque<ControlEvent<FireTarget, SwitchTarget, ValueTarget>>,
-        paused: bool,
-    ) {
-        let counter = switch_counter.entry(target).or_insert(SwitchCounter {
-            counter: 0,
-            announced_state: SwitchState::Inactive,
-        });
-        debug_assert!(counter.counter > 0, "Tried to decrease switch target counter that is {}", counter.counter);
-        counter.counter -= 1;
-        if counter.counter == 0 && !paused {
-            counter.announced_state = SwitchState::Inactive;
-            events.push_back(ControlEvent::Switch {
-                target,
-                state: SwitchState::Inactive,
-            });
+    // called once per input event with every trigger that it pressed, bound or not
+    fn advance_sequences(&mut self) {
+        if self.pressed_triggers.is_empty() {
+            return;
+        }
+        let pressed = std::mem::take(&mut self.pressed_triggers);
+        for ((steps, timeout), data) in self.sequences.iter_mut() {
+            if data.progress > 0 && self.time - data.last_step_time > *timeout {
+                data.progress = 0;
+            }
+            // continues with the longest start of the sequence that the latest presses match,
+            // so that e.g. A A A B still completes A A B
+            let progress = data.progress;
+            let matched = (1..=(progress + 1).min(steps.len())).rev()
+                .find(|&len| pressed.contains(&steps[len - 1]) && steps[..len - 1] == steps[progress + 1 - len..progress]);
+            if let Some(len) = matched {
+                data.progress = len;
+                data.last_step_time = self.time;
+                if data.progress == steps.len() {
+                    data.progress = 0;
+                    for &target in data.on_complete.iter() {
+                        self.output.fire(target);
+                    }
+                }
+            } else if !steps.get(progress)
+                .is_some_and(|expected| pressed.iter().any(|trigger| expected.has_component(trigger)))
+            {
+                // any other press breaks the sequence, unless it is the component of a chord step
+                data.progress = 0;
+            }
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use winit::event_loop::EventLoop;
-    use winit::event::Event;
-    use winit::event::WindowEvent;
-    use winit::window::Window;
-    use winit::event_loop::ControlFlow;
-
-    use strum_macros::EnumString;
-    use strum_macros::ToString;
-
-    use crate::Controls;
-    use crate::ControlBind;
-    use crate::ValueTargetTrait;
-    use crate::FireTrigger;
-    use crate::HoldableTrigger;
-    use crate::ValueTrigger;
-    use crate::MouseWheelDirection;
-    use crate::VirtualKeyCode;
-    use std::collections::vec_deque::VecDeque;
-
-    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, ToString, EnumString)]
-    enum FireTarget {
-        LMBFire,
-        MWUpFire,
-        MWDownFire,
-        GHFire,
-    }
-
-    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, ToString, EnumString)]
-    enum SwitchTarget {
-        RMBSwitch,
-        GHSwitch,
-        Key0Switch,
-        AMMBSwitch,
-    }
-
-    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, ToString, EnumString)]
-    enum ValueTarget {
-        MouseX,
-    }
-
-    impl ValueTargetTrait for ValueTarget {
-        fn base_factor(&self) -> f64 {
-            1.0
-        }
-    }
-
-    #[test]
-    fn test_all() {
-        let event_loop = EventLoop::new();
-        let _window = Window::new(&event_loop).unwrap();
-        let mut controls: Controls<FireTarget, SwitchTarget, ValueTarget> = Controls::new();
-        controls.add_bind(ControlBind::Fire(FireTrigger::Holdable(HoldableTrigger::Button(1)), FireTarget::LMBFire));
-        controls.add_bind(ControlBind::Fire(FireTrigger::Holdable(HoldableTrigger::Button(1)), FireTarget::LMBFire)); // double bind ;)
-        controls.add_bind(ControlBind::Fire(FireTrigger::MouseWheelTick(MouseWheelDirection::Up), FireTarget::MWUpFire));
-        controls.add_bind(ControlBind::Fire(FireTrigger::MouseWheelTick(MouseWheelDirection::Down), FireTarget::MWDownFire));
-        controls.add_bind(ControlBind::Fire(FireTrigger::Holdable(HoldableTrigger::KeyCode(VirtualKeyCode::G)), FireTarget::GHFire));
-        controls.add_bind(ControlBind::Fire(FireTrigger::Holdable(HoldableTrigger::KeyCode(VirtualKeyCode::H)), FireTarget::GHFire));
-
-        controls.add_bind(ControlBind::Switch(HoldableTrigger::Button(3), SwitchTarget::RMBSwitch));
-        controls.add_bind(ControlBind::Switch(HoldableTrigger::KeyCode(VirtualKeyCode::G), SwitchTarget::GHSwitch));
-        controls.add_bind(ControlBind::Switch(HoldableTrigger::KeyCode(VirtualKeyCode::G), SwitchTarget::GHSwitch)); // double bind ;)
-        controls.add_bind(ControlBind::Switch(HoldableTrigger::KeyCode(VirtualKeyCode::H), SwitchTarget::GHSwitch));
-        controls.add_bind(ControlBind::Switch(HoldableTrigger::KeyCode(VirtualKeyCode::Key0), SwitchTarget::Key0Switch));
-        controls.add_bind(ControlBind::Switch(HoldableTrigger::Button(2), SwitchTarget::AMMBSwitch));
-        controls.add_bind(ControlBind::Switch(HoldableTrigger::KeyCode(VirtualKeyCode::A), SwitchTarget::AMMBSwitch));
-
-        controls.add_bind(ControlBind::Value(ValueTrigger::Axis(0), ValueTarget::MouseX));
-
-        let mut close_requested = false;
-        let mut event_buffer = VecDeque::new();
-        event_loop.run(move |event, _, control_flow| {
-            //eprintln!("{:?}", event);
-            match event {
-                Event::DeviceEvent { device_id, event } => controls.process(device_id, event),
-                Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => close_requested = true,
-                Event::WindowEvent { event: WindowEvent::Focused(focused), .. } => {
-                    if focused {
-                        controls.resume();
-                    } else {
-                        controls.pause()
+    // takes back the plain binds of the chord's already held components,
+    // they stay suppressed until the component itself is released
+    fn suppress_components(&mut self, chord: &HoldableTrigger, completing: &HoldableTrigger) {
+        let components = match chord {
+            HoldableTrigger::Chord(components) => components,
+            _ => return,
+        };
+        for component in components.iter().filter(|&component| component != completing) {
+            if let Some(data) = self.holdable_trigger_data.get_mut(component) {
+                if data.overall_counter > 0 && !data.suppressed {
+                    data.suppressed = true;
+                    for &switch_target in data.while_down.iter() {
+                        self.output.decrease_switch_target_counter(switch_target);
                     }
-                },
-                _ => (),
+                }
             }
+        }
+    }
 
-            controls.get_events(&mut event_buffer);
-            for event in event_buffer.drain(..) {
-                eprintln!("{:?}", event);
-            }
+    // the bound chords containing the trigger that just became active or inactive because of it
+    fn changed_chords(&self, trigger: &HoldableTrigger) -> Vec<HoldableTrigger> {
+        let is_active = |trigger: &HoldableTrigger| self.holdable_trigger_data.get(trigger)
+            .is_some_and(|data| data.overall_counter > 0);
+        self.holdable_trigger_data.keys()
+            .filter(|chord| match chord {
+                HoldableTrigger::Chord(components) => components.contains(trigger)
+                    && components.iter().all(is_active) != is_active(chord),
+                _ => false,
+            })
+            .cloned()
+            .collect()
+    }
 
-            if close_requested {
-                *control_flow = ControlFlow::Exit;
-            } else {
-                *control_flow = ControlFlow::Wait;
+    // releases everything the device held, the chords are released through their components
+    fn on_device_removed(&mut self, device_id: DeviceId) {
+        let mut held: Vec<(HoldableTrigger, u32)> = self.holdable_trigger_data.iter()
+            .filter(|(trigger, _)| !matches!(trigger, HoldableTrigger::Chord(_)))
+            .filter_map(|(trigger, data)| match data.device_counters.get(&device_id) {
+                Some(&counter) if counter > 0 => Some((trigger.clone(), counter)),
+                _ => None,
+            })
+            .collect();
+        // sorted, so that the releases come in the same order every time
+        held.sort();
+        for (trigger, counter) in held {
+            for _ in 0..counter {
+                self.handle_holdable_trigger(trigger.clone(), device_id, ElementState::Released);
             }
-        });
+        }
+        self.reached_axis_thresholds.retain(|&(_, device)| device != device_id);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,159 @@
+use std::collections::VecDeque;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use super::ControlEvent;
+use super::SwitchCounter;
+use super::SwitchState;
+use super::ValueTargetTrait;
+use super::ValueProcessing;
+
+// everything between the binds and the event queue
+pub(crate) struct Output<FireTarget, SwitchTarget, ValueTarget>
+where FireTarget: Eq + Hash,
+      SwitchTarget: Eq + Hash,
+      ValueTarget: Eq + Hash,
+{
+    pub(crate) events: VecDeque<ControlEvent<FireTarget, SwitchTarget, ValueTarget>>,
+    pub(crate) switch_counters: HashMap<SwitchTarget, SwitchCounter>,
+    pub(crate) value_factors: HashMap<ValueTarget, f64>,
+    pub(crate) value_processing: HashMap<ValueTarget, ValueProcessing>,
+    pub(crate) accumulated_values: HashMap<ValueTarget, f64>,
+    pub(crate) accumulated_vectors: HashMap<ValueTarget, (f64, f64)>,
+    pub(crate) paused: bool,
+    pub(crate) disabled_fire_targets: HashSet<FireTarget>,
+    // still counted, only the announcements are held back
+    pub(crate) disabled_switch_targets: HashSet<SwitchTarget>,
+    pub(crate) disabled_value_targets: HashSet<ValueTarget>,
+}
+
+impl<FireTarget, SwitchTarget, ValueTarget> Output<FireTarget, SwitchTarget, ValueTarget>
+where FireTarget: Copy + Eq + Hash,
+      SwitchTarget: Copy + Eq + Hash,
+      ValueTarget: ValueTargetTrait + Copy + Eq + Hash,
+{
+    pub(crate) fn new() -> Self {
+        Output {
+            events: VecDeque::new(),
+            switch_counters: HashMap::new(),
+            value_factors: HashMap::new(),
+            value_processing: HashMap::new(),
+            accumulated_values: HashMap::new(),
+            accumulated_vectors: HashMap::new(),
+            paused: false,
+            disabled_fire_targets: HashSet::new(),
+            disabled_switch_targets: HashSet::new(),
+            disabled_value_targets: HashSet::new(),
+        }
+    }
+
+    pub(crate) fn fire(&mut self, target: FireTarget) {
+        if !self.paused && !self.disabled_fire_targets.contains(&target) {
+            self.events.push_back(ControlEvent::Fire(target));
+        }
+    }
+
+    // returns whether the value was announced
+    pub(crate) fn value(&mut self, target: ValueTarget, value: f64) -> bool {
+        self.announce_value(target, value).is_some()
+    }
+
+    // relative values, like mouse motion, are also summed up until the end of the frame,
+    // summing up positions would depend on how often they are reported
+    pub(crate) fn delta(&mut self, target: ValueTarget, value: f64) {
+        if let Some(value) = self.announce_value(target, value) {
+            *self.accumulated_values.entry(target).or_insert(0.0) += value;
+        }
+    }
+
+    pub(crate) fn vector(&mut self, target: ValueTarget, value: (f64, f64)) -> bool {
+        self.announce_vector(target, value).is_some()
+    }
+
+    pub(crate) fn vector_delta(&mut self, target: ValueTarget, value: (f64, f64)) {
+        if let Some(value) = self.announce_vector(target, value) {
+            let accumulated = self.accumulated_vectors.entry(target).or_insert((0.0, 0.0));
+            accumulated.0 += value.0;
+            accumulated.1 += value.1;
+        }
+    }
+
+    fn announce_value(&mut self, target: ValueTarget, value: f64) -> Option<f64> {
+        if self.paused || self.disabled_value_targets.contains(&target) {
+            return None;
+        }
+        let factor = self.value_factors.get(&target).unwrap_or(&1.0) * target.base_factor();
+        let value = match self.value_processing.get(&target) {
+            Some(processing) => processing.apply(value, factor),
+            None => value * factor,
+        };
+        self.events.push_back(ControlEvent::Value { target, value });
+        Some(value)
+    }
+
+    fn announce_vector(&mut self, target: ValueTarget, value: (f64, f64)) -> Option<(f64, f64)> {
+        if self.paused || self.disabled_value_targets.contains(&target) {
+            return None;
+        }
+        let factor = self.value_factors.get(&target).unwrap_or(&1.0) * target.base_factor();
+        let value = match self.value_processing.get(&target) {
+            Some(processing) => processing.apply_vector(value, factor),
+            None => (value.0 * factor, value.1 * factor),
+        };
+        self.events.push_back(ControlEvent::Vector { target, value });
+        Some(value)
+    }
+
+    pub(crate) fn increase_switch_target_counter(&mut self, target: SwitchTarget) {
+        let counter = self.switch_counters.entry(target).or_insert(SwitchCounter {
+            counter: 0,
+            announced_state: SwitchState::Inactive,
+        });
+        if counter.counter == 0 && !self.paused && !self.disabled_switch_targets.contains(&target) {
+            counter.announced_state = SwitchState::Active;
+            self.events.push_back(ControlEvent::Switch {
+                target,
+                state: SwitchState::Active,
+            });
+        }
+        counter.counter += 1;
+    }
+
+    pub(crate) fn decrease_switch_target_counter(&mut self, target: SwitchTarget) {
+        let counter = self.switch_counters.entry(target).or_insert(SwitchCounter {
+            counter: 0,
+            announced_state: SwitchState::Inactive,
+        });
+        debug_assert!(counter.counter > 0, "Tried to decrease switch target counter that is {}", counter.counter);
+        counter.counter -= 1;
+        if counter.counter == 0 && !self.paused && !self.disabled_switch_targets.contains(&target) {
+            counter.announced_state = SwitchState::Inactive;
+            self.events.push_back(ControlEvent::Switch {
+                target,
+                state: SwitchState::Inactive,
+            });
+        }
+    }
+
+    // announces every switch whose state changed while the announcements were suppressed,
+    // disabled switches are announced as inactive
+    pub(crate) fn announce_switch_states(&mut self) {
+        for (&target, counter) in self.switch_counters.iter_mut() {
+            let enabled = !self.disabled_switch_targets.contains(&target);
+            if enabled && counter.counter > 0 && counter.announced_state == SwitchState::Inactive {
+                counter.announced_state = SwitchState::Active;
+                self.events.push_back(ControlEvent::Switch {
+                    target,
+                    state: SwitchState::Active,
+                });
+            } else if (!enabled || counter.counter == 0) && counter.announced_state == SwitchState::Active {
+                counter.announced_state = SwitchState::Inactive;
+                self.events.push_back(ControlEvent::Switch {
+                    target,
+                    state: SwitchState::Inactive,
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::BTreeMap;
+
+use toml::Value;
+
+use super::ControlsError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResponseCurve {
+    Linear,
+    // output = input^exponent
+    Power(f64),
+    // points (input, output) with ascending inputs, linearly interpolated in between
+    Custom(Vec<(f64, f64)>),
+}
+
+impl ResponseCurve {
+    // the curve only shapes the magnitude, the sign is kept
+    fn apply(&self, value: f64) -> f64 {
+        use self::ResponseCurve::*;
+
+        let magnitude = value.abs();
+        let shaped = match self {
+            Linear => magnitude,
+            Power(exponent) => magnitude.powf(*exponent),
+            Custom(points) => interpolate(points, magnitude),
+        };
+        shaped.copysign(value)
+    }
+}
+
+// inputs with a smaller magnitude become 0, the rest is rescaled to start at 0
+fn apply_deadzone(value: f64, deadzone: f64) -> f64 {
+    let magnitude = value.abs();
+    if magnitude <= deadzone {
+        0.0
+    } else if deadzone > 0.0 && deadzone < 1.0 {
+        ((magnitude - deadzone) / (1.0 - deadzone)).copysign(value)
+    } else {
+        value
+    }
+}
+
+fn interpolate(points: &[(f64, f64)], x: f64) -> f64 {
+    let (first, last) = match (points.first(), points.last()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return x,
+    };
+    if x <= first.0 {
+        return first.1;
+    }
+    for window in points.windows(2) {
+        let (x0, y0) = window[0];
+        let (x1, y1) = window[1];
+        if x <= x1 {
+            if x1 == x0 {
+                return y1;
+            }
+            return y0 + (x - x0) / (x1 - x0) * (y1 - y0);
+        }
+    }
+    last.1
+}
+
+// applied to every value of a value target before it is queued
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueProcessing {
+    // see apply_deadzone
+    pub deadzone: f64,
+    // like the deadzone, but for the length of vectors
+    pub radial_deadzone: f64,
+    pub curve: ResponseCurve,
+    pub invert: bool,
+    // bounds of the final value, after the factors have been applied
+    pub clamp: Option<(f64, f64)>,
+}
+
+impl Default for ValueProcessing {
+    fn default() -> Self {
+        ValueProcessing {
+            deadzone: 0.0,
+            radial_deadzone: 0.0,
+            curve: ResponseCurve::Linear,
+            invert: false,
+            clamp: None,
+        }
+    }
+}
+
+impl ValueProcessing {
+    pub fn apply(&self, value: f64, factor: f64) -> f64 {
+        let mut value = apply_deadzone(value, self.deadzone);
+        value = self.curve.apply(value);
+        if self.invert {
+            value = -value;
+        }
+        value *= factor;
+        if let Some((min, max)) = self.clamp {
+            value = value.max(min).min(max);
+        }
+        value
+    }
+
+    // the deadzone is applied to each component, the radial deadzone and the curve to the length
+    pub fn apply_vector(&self, value: (f64, f64), factor: f64) -> (f64, f64) {
+        let (x, y) = (apply_deadzone(value.0, self.deadzone), apply_deadzone(value.1, self.deadzone));
+        let length = x.hypot(y);
+        if length == 0.0 {
+            return (0.0, 0.0);
+        }
+        let mut scale = self.curve.apply(apply_deadzone(length, self.radial_deadzone)) / length * factor;
+        if self.invert {
+            scale = -scale;
+        }
+        let (mut x, mut y) = (x * scale, y * scale);
+        if let Some((min, max)) = self.clamp {
+            x = x.max(min).min(max);
+            y = y.max(min).min(max);
+        }
+        (x, y)
+    }
+
+    pub fn from_toml(value: &Value) -> Result<ValueProcessing, ControlsError> {
+        let table = match value {
+            Value::Table(table) => table,
+            v => return Err(ControlsError::ExpectedTable { path: String::new(), value: v.clone() }),
+        };
+        let mut processing = ValueProcessing::default();
+        for (key, value) in table {
+            let invalid = || ControlsError::InvalidProcessing { path: key.clone(), value: value.clone() };
+            match (key.as_ref(), value) {
+                ("deadzone", &Value::Float(deadzone)) if deadzone >= 0.0 => processing.deadzone = deadzone,
+                ("radial_deadzone", &Value::Float(deadzone)) if deadzone >= 0.0 =>
+                    processing.radial_deadzone = deadzone,
+                // 0 would turn a resting stick into full input, negative ones into infinity
+                ("curve", &Value::Float(exponent)) if exponent > 0.0 => processing.curve = ResponseCurve::Power(exponent),
+                ("curve", Value::Array(points)) => {
+                    let points = points.iter()
+                        .map(|point| match point.as_array().map(Vec::as_slice) {
+                            Some(&[Value::Float(x), Value::Float(y)]) => Some((x, y)),
+                            _ => None,
+                        })
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(invalid)?;
+                    if points.windows(2).any(|window| window[0].0 > window[1].0) {
+                        return Err(invalid());
+                    }
+                    processing.curve = ResponseCurve::Custom(points);
+                },
+                ("invert", &Value::Boolean(invert)) => processing.invert = invert,
+                ("clamp", Value::Array(bounds)) => match bounds.as_slice() {
+                    &[Value::Float(min), Value::Float(max)] if min <= max => processing.clamp = Some((min, max)),
+                    _ => return Err(invalid()),
+                },
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(processing)
+    }
+
+    pub fn to_toml(&self) -> Value {
+        let mut table = BTreeMap::new();
+        if self.deadzone != 0.0 {
+            table.insert(String::from("deadzone"), Value::Float(self.deadzone));
+        }
+        if self.radial_deadzone != 0.0 {
+            table.insert(String::from("radial_deadzone"), Value::Float(self.radial_deadzone));
+        }
+        match self.curve {
+            ResponseCurve::Linear => (),
+            ResponseCurve::Power(exponent) => {
+                table.insert(String::from("curve"), Value::Float(exponent));
+            },
+            ResponseCurve::Custom(ref points) => {
+                let points = points.iter()
+                    .map(|&(x, y)| Value::Array(vec![Value::Float(x), Value::Float(y)]))
+                    .collect();
+                table.insert(String::from("curve"), Value::Array(points));
+            },
+        }
+        if self.invert {
+            table.insert(String::from("invert"), Value::Boolean(true));
+        }
+        if let Some((min, max)) = self.clamp {
+            table.insert(String::from("clamp"), Value::Array(vec![Value::Float(min), Value::Float(max)]));
+        }
+        Value::Table(table)
+    }
+}
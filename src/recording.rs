@@ -0,0 +1,368 @@
+use std::hash::Hash;
+use std::str::FromStr;
+use std::string::ToString;
+use std::time::Duration;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+
+use super::Controls;
+use super::ControlsError;
+use super::DeviceId;
+use super::ElementState;
+use super::InputEvent;
+use super::Target;
+use super::ValueTargetTrait;
+use super::keys::KEY_CODE_PAIRS;
+use super::gamepad::GAMEPAD_AXIS_PAIRS;
+use super::gamepad::GAMEPAD_BUTTON_PAIRS;
+
+const MAGIC: &[u8] = b"CTRLREC";
+// bumped whenever the format changes
+const VERSION: u8 = 2;
+
+const TICK: u8 = 0;
+const KEY: u8 = 1;
+const BUTTON: u8 = 2;
+const MOUSE_WHEEL: u8 = 3;
+const MOUSE_MOTION: u8 = 4;
+const MOTION: u8 = 5;
+const GAMEPAD_BUTTON: u8 = 6;
+const GAMEPAD_AXIS: u8 = 7;
+const REMOVED: u8 = 8;
+const PAUSE: u8 = 9;
+const RESUME: u8 = 10;
+const RESET_HELD_STATE: u8 = 11;
+const PUSH_CONTEXT: u8 = 12;
+const POP_CONTEXT: u8 = 13;
+const ENABLE_TARGET: u8 = 14;
+const DISABLE_TARGET: u8 = 15;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordedInput {
+    Tick(Duration),
+    // the time is the one of the controls clock when the event was processed
+    Event { time: Duration, device_id: DeviceId, event: InputEvent },
+    Pause,
+    Resume,
+    ResetHeldState,
+    PushContext(String),
+    PopContext,
+    // targets are stored by name, like in the config
+    EnableTarget(String),
+    DisableTarget(String),
+}
+
+// the ticks, the events that weren't swallowed by a capture and the calls that change
+// which binds fire while recording, replaying it on controls with the same binds,
+// contexts and settings produces the same control events.
+// changes to binds, contexts, settings and device assignments aren't recorded
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Recording {
+    inputs: Vec<RecordedInput>,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Recording {
+            inputs: Vec::new(),
+        }
+    }
+
+    pub fn inputs(&self) -> &[RecordedInput] {
+        &self.inputs
+    }
+
+    pub(crate) fn push(&mut self, input: RecordedInput) {
+        self.inputs.push(input);
+    }
+
+    // fails on a target the controls don't know, the inputs before it are replayed
+    pub fn replay<FireTarget, SwitchTarget, ValueTarget>(
+        &self,
+        controls: &mut Controls<FireTarget, SwitchTarget, ValueTarget>,
+    ) -> Result<(), ControlsError>
+    where FireTarget: Copy + Eq + Hash + FromStr + ToString,
+          SwitchTarget: Copy + Eq + Hash + FromStr + ToString,
+          ValueTarget: ValueTargetTrait + Copy + Eq + Hash + FromStr + ToString,
+    {
+        for input in self.inputs.iter() {
+            match *input {
+                RecordedInput::Tick(dt) => controls.tick(dt),
+                RecordedInput::Event { device_id, event, .. } => controls.process(device_id, event),
+                RecordedInput::Pause => controls.pause(),
+                RecordedInput::Resume => controls.resume(),
+                RecordedInput::ResetHeldState => controls.reset_held_state(),
+                RecordedInput::PushContext(ref name) => controls.push_context(name),
+                RecordedInput::PopContext => {
+                    controls.pop_context();
+                },
+                RecordedInput::EnableTarget(ref name) => controls.enable_target(name.parse::<Target<_, _, _>>()?),
+                RecordedInput::DisableTarget(ref name) => controls.disable_target(name.parse::<Target<_, _, _>>()?),
+            }
+        }
+        Ok(())
+    }
+
+    // little endian, floats are stored bit for bit so that replays are exact
+    pub fn to_bytes(&self) -> Vec<u8> {
+        use self::InputEvent::*;
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        for input in self.inputs.iter() {
+            let (time, device_id, event) = match *input {
+                RecordedInput::Tick(dt) => {
+                    bytes.push(TICK);
+                    write_duration(&mut bytes, dt);
+                    continue;
+                },
+                RecordedInput::Event { time, device_id, event } => (time, device_id, event),
+                RecordedInput::Pause => {
+                    bytes.push(PAUSE);
+                    continue;
+                },
+                RecordedInput::Resume => {
+                    bytes.push(RESUME);
+                    continue;
+                },
+                RecordedInput::ResetHeldState => {
+                    bytes.push(RESET_HELD_STATE);
+                    continue;
+                },
+                RecordedInput::PushContext(ref name) => {
+                    bytes.push(PUSH_CONTEXT);
+                    write_text(&mut bytes, name);
+                    continue;
+                },
+                RecordedInput::PopContext => {
+                    bytes.push(POP_CONTEXT);
+                    continue;
+                },
+                RecordedInput::EnableTarget(ref name) => {
+                    bytes.push(ENABLE_TARGET);
+                    write_text(&mut bytes, name);
+                    continue;
+                },
+                RecordedInput::DisableTarget(ref name) => {
+                    bytes.push(DISABLE_TARGET);
+                    write_text(&mut bytes, name);
+                    continue;
+                },
+            };
+            let start = bytes.len();
+            bytes.push(0);
+            write_duration(&mut bytes, time);
+            bytes.extend_from_slice(&device_id.0.to_le_bytes());
+            bytes[start] = match event {
+                Key { scan_code, key_code, state } => {
+                    bytes.extend_from_slice(&scan_code.to_le_bytes());
+                    let name = key_code
+                        .and_then(|key_code| KEY_CODE_PAIRS.iter().find(|&&(kc, _)| kc == key_code))
+                        .map(|&(_, name)| name)
+                        .unwrap_or("");
+                    write_name(&mut bytes, name);
+                    write_state(&mut bytes, state);
+                    KEY
+                },
+                Button { button, state } => {
+                    bytes.extend_from_slice(&button.to_le_bytes());
+                    write_state(&mut bytes, state);
+                    BUTTON
+                },
+                MouseWheel { delta } => {
+                    bytes.extend_from_slice(&delta.to_le_bytes());
+                    MOUSE_WHEEL
+                },
+                MouseMotion { delta: (dx, dy) } => {
+                    bytes.extend_from_slice(&dx.to_le_bytes());
+                    bytes.extend_from_slice(&dy.to_le_bytes());
+                    MOUSE_MOTION
+                },
+                Motion { axis, value } => {
+                    bytes.extend_from_slice(&axis.to_le_bytes());
+                    bytes.extend_from_slice(&value.to_le_bytes());
+                    MOTION
+                },
+                GamepadButton { button, state } => {
+                    let &(_, name) = GAMEPAD_BUTTON_PAIRS.iter().find(|&&(b, _)| b == button).unwrap();
+                    write_name(&mut bytes, name);
+                    write_state(&mut bytes, state);
+                    GAMEPAD_BUTTON
+                },
+                GamepadAxis { axis, value } => {
+                    let &(_, name) = GAMEPAD_AXIS_PAIRS.iter().find(|&&(a, _)| a == axis).unwrap();
+                    write_name(&mut bytes, name);
+                    bytes.extend_from_slice(&value.to_le_bytes());
+                    GAMEPAD_AXIS
+                },
+                Removed => REMOVED,
+            };
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let mut reader = ByteReader { bytes };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(invalid_data("Not a recording"));
+        }
+        if reader.u8()? != VERSION {
+            return Err(invalid_data("Unsupported recording version"));
+        }
+        let mut recording = Recording::new();
+        while !reader.bytes.is_empty() {
+            let tag = reader.u8()?;
+            let input = match tag {
+                TICK => Some(RecordedInput::Tick(reader.duration()?)),
+                PAUSE => Some(RecordedInput::Pause),
+                RESUME => Some(RecordedInput::Resume),
+                RESET_HELD_STATE => Some(RecordedInput::ResetHeldState),
+                PUSH_CONTEXT => Some(RecordedInput::PushContext(String::from(reader.text()?))),
+                POP_CONTEXT => Some(RecordedInput::PopContext),
+                ENABLE_TARGET => Some(RecordedInput::EnableTarget(String::from(reader.text()?))),
+                DISABLE_TARGET => Some(RecordedInput::DisableTarget(String::from(reader.text()?))),
+                _ => None,
+            };
+            if let Some(input) = input {
+                recording.push(input);
+                continue;
+            }
+            let time = reader.duration()?;
+            let device_id = DeviceId(reader.u64()?);
+            let event = match tag {
+                KEY => {
+                    let scan_code = reader.u32()?;
+                    let name = reader.name()?;
+                    let key_code = if name.is_empty() {
+                        None
+                    } else {
+                        let &(key_code, _) = KEY_CODE_PAIRS.iter()
+                            .find(|&&(_, kc_name)| kc_name == name)
+                            .ok_or_else(|| invalid_data("Unknown key code"))?;
+                        Some(key_code)
+                    };
+                    InputEvent::Key { scan_code, key_code, state: reader.state()? }
+                },
+                BUTTON => InputEvent::Button { button: reader.u32()?, state: reader.state()? },
+                MOUSE_WHEEL => InputEvent::MouseWheel { delta: reader.f64()? },
+                MOUSE_MOTION => InputEvent::MouseMotion { delta: (reader.f64()?, reader.f64()?) },
+                MOTION => InputEvent::Motion { axis: reader.u32()?, value: reader.f64()? },
+                GAMEPAD_BUTTON => {
+                    let name = reader.name()?;
+                    let &(button, _) = GAMEPAD_BUTTON_PAIRS.iter()
+                        .find(|&&(_, button_name)| button_name == name)
+                        .ok_or_else(|| invalid_data("Unknown gamepad button"))?;
+                    InputEvent::GamepadButton { button, state: reader.state()? }
+                },
+                GAMEPAD_AXIS => {
+                    let name = reader.name()?;
+                    let &(axis, _) = GAMEPAD_AXIS_PAIRS.iter()
+                        .find(|&&(_, axis_name)| axis_name == name)
+                        .ok_or_else(|| invalid_data("Unknown gamepad axis"))?;
+                    InputEvent::GamepadAxis { axis, value: reader.f64()? }
+                },
+                REMOVED => InputEvent::Removed,
+                _ => return Err(invalid_data("Unknown input kind")),
+            };
+            recording.push(RecordedInput::Event { time, device_id, event });
+        }
+        Ok(recording)
+    }
+
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+fn write_duration(bytes: &mut Vec<u8>, duration: Duration) {
+    bytes.extend_from_slice(&(duration.as_nanos() as u64).to_le_bytes());
+}
+
+// names instead of indices, so that recordings survive new keys being added
+fn write_name(bytes: &mut Vec<u8>, name: &str) {
+    bytes.push(name.len() as u8);
+    bytes.extend_from_slice(name.as_bytes());
+}
+
+// context and target names aren't limited in length like key names
+fn write_text(bytes: &mut Vec<u8>, text: &str) {
+    bytes.extend_from_slice(&(text.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(text.as_bytes());
+}
+
+fn write_state(bytes: &mut Vec<u8>, state: ElementState) {
+    bytes.push(match state {
+        ElementState::Pressed => 1,
+        ElementState::Released => 0,
+    });
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Recording ends in the middle of an input"));
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    fn f64(&mut self) -> io::Result<f64> {
+        self.array().map(f64::from_le_bytes)
+    }
+
+    fn duration(&mut self) -> io::Result<Duration> {
+        self.u64().map(Duration::from_nanos)
+    }
+
+    fn name(&mut self) -> io::Result<&'a str> {
+        let len = self.u8()? as usize;
+        std::str::from_utf8(self.take(len)?).map_err(|_| invalid_data("Invalid name"))
+    }
+
+    fn text(&mut self) -> io::Result<&'a str> {
+        let len = self.u32()? as usize;
+        std::str::from_utf8(self.take(len)?).map_err(|_| invalid_data("Invalid text"))
+    }
+
+    fn state(&mut self) -> io::Result<ElementState> {
+        match self.u8()? {
+            1 => Ok(ElementState::Pressed),
+            0 => Ok(ElementState::Released),
+            _ => Err(invalid_data("Invalid element state")),
+        }
+    }
+}
@@ -0,0 +1,9 @@
+use super::DeviceId;
+
+// which devices a scoped bind listens to, e.g. one per player for local multiplayer
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BindScope {
+    Device(DeviceId),
+    // every device that is assigned to the slot
+    Slot(u32),
+}
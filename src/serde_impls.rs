@@ -0,0 +1,140 @@
+use std::hash::Hash;
+use std::str::FromStr;
+use std::string::ToString;
+
+use serde::de;
+use serde::ser;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+use super::ControlBind;
+use super::Controls;
+use super::ControlsError;
+use super::FireTrigger;
+use super::HoldableTrigger;
+use super::MouseWheelDirection;
+use super::ValueTargetTrait;
+use super::ValueTrigger;
+use super::VectorTrigger;
+
+// everything goes through the TOML representation, so every format uses the same names as the config files.
+// formats that aren't self-describing, like bincode, can't deserialize a TOML value without knowing its type,
+// so they get the TOML text of a document with the value as its only entry
+fn serialize_toml<S: Serializer>(value: toml::Value, serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        value.serialize(serializer)
+    } else {
+        let mut document = toml::value::Table::new();
+        document.insert(String::from("value"), value);
+        toml::to_string(&document).map_err(ser::Error::custom)?.serialize(serializer)
+    }
+}
+
+fn deserialize_toml<'de, D: Deserializer<'de>>(deserializer: D) -> Result<toml::Value, D::Error> {
+    if deserializer.is_human_readable() {
+        toml::Value::deserialize(deserializer)
+    } else {
+        let text = String::deserialize(deserializer)?;
+        let mut document: toml::value::Table = toml::from_str(&text).map_err(de::Error::custom)?;
+        document.remove("value").ok_or_else(|| de::Error::missing_field("value"))
+    }
+}
+
+macro_rules! toml_serde {
+    ($($type:ty),*) => {
+        $(
+            impl Serialize for $type {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    serialize_toml(self.to_toml(), serializer)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $type {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    let value = deserialize_toml(deserializer)?;
+                    Self::from_toml(&value).map_err(de::Error::custom)
+                }
+            }
+        )*
+    };
+}
+
+toml_serde!(FireTrigger, HoldableTrigger, ValueTrigger, VectorTrigger);
+
+impl Serialize for MouseWheelDirection {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            MouseWheelDirection::Up => serializer.serialize_str("Up"),
+            MouseWheelDirection::Down => serializer.serialize_str("Down"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for MouseWheelDirection {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match String::deserialize(deserializer)?.as_ref() {
+            "Up" => Ok(MouseWheelDirection::Up),
+            "Down" => Ok(MouseWheelDirection::Down),
+            other => Err(de::Error::unknown_variant(other, &["Up", "Down"])),
+        }
+    }
+}
+
+// a bind is a table with a single entry, like in the binds section of the config
+impl<FireTarget, SwitchTarget, ValueTarget> Serialize for ControlBind<FireTarget, SwitchTarget, ValueTarget>
+where FireTarget: Copy + Eq + Hash + FromStr + ToString,
+      SwitchTarget: Copy + Eq + Hash + FromStr + ToString,
+      ValueTarget: ValueTargetTrait + Copy + Eq + Hash + FromStr + ToString,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_toml(Controls::binds_to_toml(std::slice::from_ref(self)), serializer)
+    }
+}
+
+impl<'de, FireTarget, SwitchTarget, ValueTarget> Deserialize<'de> for ControlBind<FireTarget, SwitchTarget, ValueTarget>
+where FireTarget: Copy + Eq + Hash + FromStr + ToString,
+      SwitchTarget: Copy + Eq + Hash + FromStr + ToString,
+      ValueTarget: ValueTargetTrait + Copy + Eq + Hash + FromStr + ToString,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = deserialize_toml(deserializer)?;
+        let mut binds = Controls::binds_from_toml(&value, "bind", &mut Err).map_err(de::Error::custom)?;
+        if binds.len() == 1 {
+            Ok(binds.remove(0))
+        } else {
+            Err(de::Error::invalid_length(binds.len(), &"a single bind"))
+        }
+    }
+}
+
+// the binds and settings of some Controls, without any input state
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlsSnapshot(toml::Value);
+
+impl Serialize for ControlsSnapshot {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_toml(self.0.clone(), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ControlsSnapshot {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_toml(deserializer).map(ControlsSnapshot)
+    }
+}
+
+impl<FireTarget, SwitchTarget, ValueTarget> Controls<FireTarget, SwitchTarget, ValueTarget>
+where FireTarget: Copy + Eq + Hash + FromStr + ToString,
+      SwitchTarget: Copy + Eq + Hash + FromStr + ToString,
+      ValueTarget: ValueTargetTrait + Copy + Eq + Hash + FromStr + ToString,
+{
+    pub fn snapshot(&self) -> ControlsSnapshot {
+        ControlsSnapshot(self.to_toml())
+    }
+
+    pub fn from_snapshot(snapshot: &ControlsSnapshot) -> Result<Self, ControlsError> {
+        Self::from_toml(&snapshot.0)
+    }
+}
@@ -0,0 +1,1456 @@
+use strum_macros::EnumString;
+use strum_macros::Display;
+
+use std::collections::VecDeque;
+
+use crate::Controls;
+use crate::ControlBind;
+use crate::ControlEvent;
+use crate::ControlsError;
+use crate::DeviceId;
+use crate::ElementState;
+use crate::GamepadAxis;
+use crate::GamepadButton;
+use crate::InputEvent;
+use crate::SwitchState;
+use crate::Target;
+use crate::TargetKind;
+use crate::CaptureOptions;
+use crate::CapturedTrigger;
+use crate::Recording;
+use crate::RecordedInput;
+use crate::BindScope;
+use crate::PauseMode;
+use crate::AxisThreshold;
+use crate::ThresholdAxis;
+use crate::ValueTargetTrait;
+use crate::ValueProcessing;
+use crate::FireTrigger;
+use crate::HoldableTrigger;
+use crate::ValueTrigger;
+use crate::VectorTrigger;
+use crate::MouseWheelDirection;
+use crate::VirtualKeyCode;
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Display, EnumString)]
+enum FireTarget {
+    LMBFire,
+    MWUpFire,
+    MWDownFire,
+    GHFire,
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Display, EnumString)]
+enum SwitchTarget {
+    RMBSwitch,
+    GHSwitch,
+    Key0Switch,
+    AMMBSwitch,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Display, EnumString)]
+enum ValueTarget {
+    MouseX,
+}
+
+impl ValueTargetTrait for ValueTarget {
+    fn base_factor(&self) -> f64 {
+        1.0
+    }
+}
+
+type TestControls = Controls<FireTarget, SwitchTarget, ValueTarget>;
+type TestEvent = ControlEvent<FireTarget, SwitchTarget, ValueTarget>;
+type TestTarget = Target<FireTarget, SwitchTarget, ValueTarget>;
+
+const KEYBOARD: DeviceId = DeviceId(1);
+const MOUSE: DeviceId = DeviceId(2);
+const OTHER_KEYBOARD: DeviceId = DeviceId(3);
+const OTHER_MOUSE: DeviceId = DeviceId(4);
+const GAMEPAD: DeviceId = DeviceId(5);
+const OTHER_GAMEPAD: DeviceId = DeviceId(6);
+
+fn key(key_code: VirtualKeyCode, state: ElementState) -> InputEvent {
+    // the scan codes don't matter, since no test binds them
+    InputEvent::Key { scan_code: key_code as u32, key_code: Some(key_code), state }
+}
+
+fn button(button: u32, state: ElementState) -> InputEvent {
+    InputEvent::Button { button, state }
+}
+
+fn events(controls: &mut TestControls) -> Vec<TestEvent> {
+    let mut events = VecDeque::new();
+    controls.get_events(&mut events);
+    events.into_iter().collect()
+}
+
+fn switch(target: SwitchTarget, state: SwitchState) -> TestEvent {
+    ControlEvent::Switch { target, state }
+}
+
+fn create_controls() -> TestControls {
+    let mut controls = Controls::new();
+    controls.add_bind(ControlBind::Fire(FireTrigger::Holdable(HoldableTrigger::Button(1)), FireTarget::LMBFire));
+    controls.add_bind(ControlBind::Fire(FireTrigger::Holdable(HoldableTrigger::Button(1)), FireTarget::LMBFire)); // double bind ;)
+    controls.add_bind(ControlBind::Fire(FireTrigger::MouseWheelTick(MouseWheelDirection::Up), FireTarget::MWUpFire));
+    controls.add_bind(ControlBind::Fire(FireTrigger::MouseWheelTick(MouseWheelDirection::Down), FireTarget::MWDownFire));
+    controls.add_bind(ControlBind::Fire(FireTrigger::Holdable(HoldableTrigger::KeyCode(VirtualKeyCode::G)), FireTarget::GHFire));
+    controls.add_bind(ControlBind::Fire(FireTrigger::Holdable(HoldableTrigger::KeyCode(VirtualKeyCode::H)), FireTarget::GHFire));
+
+    controls.add_bind(ControlBind::Switch(HoldableTrigger::Button(3), SwitchTarget::RMBSwitch));
+    controls.add_bind(ControlBind::Switch(HoldableTrigger::KeyCode(VirtualKeyCode::G), SwitchTarget::GHSwitch));
+    controls.add_bind(ControlBind::Switch(HoldableTrigger::KeyCode(VirtualKeyCode::G), SwitchTarget::GHSwitch)); // double bind ;)
+    controls.add_bind(ControlBind::Switch(HoldableTrigger::KeyCode(VirtualKeyCode::H), SwitchTarget::GHSwitch));
+    controls.add_bind(ControlBind::Switch(HoldableTrigger::KeyCode(VirtualKeyCode::Key0), SwitchTarget::Key0Switch));
+    controls.add_bind(ControlBind::Switch(HoldableTrigger::Button(2), SwitchTarget::AMMBSwitch));
+    controls.add_bind(ControlBind::Switch(HoldableTrigger::KeyCode(VirtualKeyCode::A), SwitchTarget::AMMBSwitch));
+
+    controls.add_bind(ControlBind::Value(ValueTrigger::Axis(0), ValueTarget::MouseX));
+    controls
+}
+
+#[test]
+fn test_toml_round_trip() {
+    let controls = create_controls();
+    let toml = controls.to_toml();
+    assert_eq!(
+        toml["binds"]["GHFire"],
+        toml::Value::Array(vec![
+            toml::Value::String(String::from("G")),
+            toml::Value::String(String::from("H")),
+        ]),
+    );
+    let parsed = TestControls::from_toml(&toml).unwrap();
+    assert_eq!(parsed.get_binds().len(), controls.get_binds().len());
+    assert_eq!(parsed.to_toml(), toml);
+}
+
+#[test]
+fn test_toml_text_round_trip() {
+    use crate::VirtualKeyCode::*;
+
+    let mut controls = create_controls();
+    controls.add_bind(ControlBind::Fire(FireTrigger::Holdable(HoldableTrigger::ScanCode(34)), FireTarget::GHFire));
+    controls.add_bind(ControlBind::Fire(FireTrigger::Release(HoldableTrigger::KeyCode(J)), FireTarget::GHFire));
+    controls.add_bind(ControlBind::Value(ValueTrigger::MouseX, ValueTarget::MouseX));
+    controls.add_bind(ControlBind::Toggle(HoldableTrigger::KeyCode(T), SwitchTarget::GHSwitch));
+    let toml = controls.to_toml();
+    assert_eq!(toml["binds"]["MouseX"][1]["trigger"], toml::Value::Integer(0));
+    assert_eq!(toml["binds"]["MouseX"][0]["trigger"], toml::Value::String(String::from("MouseX")));
+
+    let text = toml::to_string(&toml).unwrap();
+    let parsed = TestControls::from_toml(&toml::from_str(&text).unwrap()).unwrap();
+    assert_eq!(parsed.get_binds().len(), controls.get_binds().len());
+    assert_eq!(parsed.to_toml(), toml);
+}
+
+#[test]
+fn test_mouse_motion_toml_round_trip() {
+    for (trigger, name, delta, value) in [
+        (ValueTrigger::MouseX, "MouseX", (2.0, -1.0), 2.0),
+        (ValueTrigger::MouseY, "MouseY", (2.0, -1.0), -1.0),
+    ] {
+        let mut controls = TestControls::new();
+        controls.add_bind(ControlBind::Value(trigger.clone(), ValueTarget::MouseX));
+        let toml = controls.to_toml();
+        assert_eq!(toml["binds"]["MouseX"], toml::Value::String(String::from(name)));
+
+        let mut parsed = TestControls::from_toml(&toml).unwrap();
+        assert_eq!(parsed.get_binds(), vec![ControlBind::Value(trigger, ValueTarget::MouseX)]);
+        parsed.process(MOUSE, InputEvent::MouseMotion { delta });
+        assert_eq!(events(&mut parsed), vec![ControlEvent::Value { target: ValueTarget::MouseX, value }]);
+    }
+}
+
+#[test]
+fn test_toml_errors() {
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        GHFire = ["G", "NoSuchKey"]
+        [factors]
+    "#).unwrap();
+    let error = TestControls::from_toml(&toml).err().unwrap();
+    assert_eq!(error, ControlsError::UnknownTrigger {
+        path: String::from("binds.GHFire[1]"),
+        value: toml::Value::String(String::from("NoSuchKey")),
+    });
+
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        [factors]
+        LMBFire = 2.0
+    "#).unwrap();
+    let error = TestControls::from_toml(&toml).err().unwrap();
+    assert_eq!(error.path(), "factors.LMBFire");
+    assert_eq!(error.to_string(), "factors.LMBFire: Expected value target, got 'LMBFire'!");
+
+    let toml: toml::Value = toml::from_str("[binds]").unwrap();
+    let error = TestControls::from_toml(&toml).err().unwrap();
+    assert_eq!(error, ControlsError::MissingSection { path: String::from("factors") });
+}
+
+#[test]
+fn test_toml_lenient() {
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        GHFire = ["G", "NoSuchKey"]
+        NoSuchTarget = "H"
+        RMBSwitch = "Button3"
+        [factors]
+        MouseX = "fast"
+    "#).unwrap();
+    let (controls, warnings) = TestControls::from_toml_lenient(&toml);
+    assert_eq!(controls.get_binds().len(), 2);
+    let paths: Vec<_> = warnings.iter().map(ControlsError::path).collect();
+    assert_eq!(paths, vec!["binds.GHFire[1]", "binds.NoSuchTarget", "factors.MouseX"]);
+}
+
+#[test]
+fn test_fire_double_bind() {
+    use crate::ElementState::*;
+
+    let mut controls = create_controls();
+    controls.process(MOUSE, button(1, Pressed));
+    assert_eq!(events(&mut controls), vec![ControlEvent::Fire(FireTarget::LMBFire)]);
+    controls.process(MOUSE, button(1, Released));
+    assert_eq!(events(&mut controls), vec![]);
+}
+
+#[test]
+fn test_switch_double_bind() {
+    use crate::ElementState::*;
+    use crate::SwitchState::*;
+
+    let mut controls = create_controls();
+    controls.process(KEYBOARD, key(VirtualKeyCode::G, Pressed));
+    assert_eq!(events(&mut controls), vec![
+        ControlEvent::Fire(FireTarget::GHFire),
+        switch(SwitchTarget::GHSwitch, Active),
+    ]);
+    controls.process(KEYBOARD, key(VirtualKeyCode::H, Pressed));
+    assert_eq!(events(&mut controls), vec![ControlEvent::Fire(FireTarget::GHFire)]);
+    controls.process(KEYBOARD, key(VirtualKeyCode::G, Released));
+    assert_eq!(events(&mut controls), vec![]);
+    controls.process(KEYBOARD, key(VirtualKeyCode::H, Released));
+    assert_eq!(events(&mut controls), vec![switch(SwitchTarget::GHSwitch, Inactive)]);
+}
+
+#[test]
+fn test_switch_shared_by_key_and_button() {
+    use crate::ElementState::*;
+    use crate::SwitchState::*;
+
+    let mut controls = create_controls();
+    controls.process(MOUSE, button(2, Pressed));
+    controls.process(KEYBOARD, key(VirtualKeyCode::A, Pressed));
+    controls.process(MOUSE, button(2, Released));
+    assert_eq!(events(&mut controls), vec![switch(SwitchTarget::AMMBSwitch, Active)]);
+    controls.process(KEYBOARD, key(VirtualKeyCode::A, Released));
+    assert_eq!(events(&mut controls), vec![switch(SwitchTarget::AMMBSwitch, Inactive)]);
+}
+
+#[test]
+fn test_multi_device_counters() {
+    use crate::ElementState::*;
+    use crate::SwitchState::*;
+
+    let mut controls = create_controls();
+    controls.process(MOUSE, button(3, Pressed));
+    controls.process(OTHER_MOUSE, button(3, Pressed));
+    assert_eq!(events(&mut controls), vec![switch(SwitchTarget::RMBSwitch, Active)]);
+
+    // a device can't release what it never pressed
+    controls.process(KEYBOARD, key(VirtualKeyCode::Key0, Released));
+    controls.process(OTHER_KEYBOARD, button(3, Released));
+    assert_eq!(events(&mut controls), vec![]);
+
+    controls.process(MOUSE, button(3, Released));
+    assert_eq!(events(&mut controls), vec![]);
+    controls.process(OTHER_MOUSE, button(3, Released));
+    assert_eq!(events(&mut controls), vec![switch(SwitchTarget::RMBSwitch, Inactive)]);
+}
+
+#[test]
+fn test_device_removed() {
+    use crate::ElementState::*;
+
+    let mut controls = create_controls();
+    controls.add_bind(ControlBind::Fire(FireTrigger::Release(HoldableTrigger::Button(1)), FireTarget::GHFire));
+    controls.process(MOUSE, button(1, Pressed));
+    controls.process(MOUSE, button(3, Pressed));
+    controls.process(OTHER_MOUSE, button(3, Pressed));
+    events(&mut controls);
+
+    // released like the real thing, the switch is still held by the other mouse
+    controls.process(MOUSE, InputEvent::Removed);
+    assert_eq!(events(&mut controls), vec![
+        ControlEvent::Fire(FireTarget::GHFire),
+        ControlEvent::DeviceDisconnected(MOUSE),
+    ]);
+    controls.process(MOUSE, button(1, Released));
+    assert_eq!(events(&mut controls), vec![]);
+    controls.process(OTHER_MOUSE, InputEvent::Removed);
+    assert_eq!(events(&mut controls), vec![
+        switch(SwitchTarget::RMBSwitch, SwitchState::Inactive),
+        ControlEvent::DeviceDisconnected(OTHER_MOUSE),
+    ]);
+    assert!(!controls.is_active(SwitchTarget::RMBSwitch));
+
+    // the press counter was reset, so the next press fires again
+    controls.process(OTHER_MOUSE, button(1, Pressed));
+    assert_eq!(events(&mut controls), vec![ControlEvent::Fire(FireTarget::LMBFire)]);
+}
+
+#[test]
+fn test_mouse_wheel_and_motion() {
+    let mut controls = create_controls();
+    controls.process(MOUSE, InputEvent::MouseWheel { delta: -1.0 });
+    assert_eq!(events(&mut controls), vec![ControlEvent::Fire(FireTarget::MWUpFire)]);
+    controls.process(MOUSE, InputEvent::MouseWheel { delta: 1.0 });
+    assert_eq!(events(&mut controls), vec![ControlEvent::Fire(FireTarget::MWDownFire)]);
+
+    controls.set_factor(ValueTarget::MouseX, 2.0);
+    controls.process(MOUSE, InputEvent::Motion { axis: 0, value: 1.5 });
+    controls.process(MOUSE, InputEvent::Motion { axis: 1, value: 1.5 });
+    assert_eq!(events(&mut controls), vec![
+        ControlEvent::Value { target: ValueTarget::MouseX, value: 3.0 },
+    ]);
+
+    controls.remove_bind(ControlBind::Value(ValueTrigger::Axis(0), ValueTarget::MouseX));
+    controls.add_bind(ControlBind::Value(ValueTrigger::MouseY, ValueTarget::MouseX));
+    controls.process(MOUSE, InputEvent::MouseMotion { delta: (5.0, -1.0) });
+    assert_eq!(events(&mut controls), vec![
+        ControlEvent::Value { target: ValueTarget::MouseX, value: -2.0 },
+    ]);
+}
+
+#[test]
+fn test_pause_resume() {
+    use crate::ElementState::*;
+    use crate::SwitchState::*;
+
+    let mut controls = create_controls();
+    controls.pause();
+    controls.process(KEYBOARD, key(VirtualKeyCode::Key0, Pressed));
+    controls.process(MOUSE, button(1, Pressed));
+    assert_eq!(events(&mut controls), vec![]);
+    controls.resume();
+    assert_eq!(events(&mut controls), vec![switch(SwitchTarget::Key0Switch, Active)]);
+
+    controls.pause();
+    controls.process(KEYBOARD, key(VirtualKeyCode::Key0, Released));
+    assert_eq!(events(&mut controls), vec![]);
+    controls.resume();
+    assert_eq!(events(&mut controls), vec![switch(SwitchTarget::Key0Switch, Inactive)]);
+}
+
+#[test]
+fn test_reset_held_state() {
+    use crate::ElementState::*;
+    use crate::SwitchState::*;
+
+    let mut controls = create_controls();
+    controls.process(KEYBOARD, key(VirtualKeyCode::Key0, Pressed));
+    controls.process(MOUSE, button(3, Pressed));
+    controls.process(MOUSE, button(1, Pressed));
+    events(&mut controls);
+    controls.reset_held_state();
+    let released = events(&mut controls);
+    assert_eq!(released.len(), 2);
+    assert!(released.contains(&switch(SwitchTarget::Key0Switch, Inactive)));
+    assert!(released.contains(&switch(SwitchTarget::RMBSwitch, Inactive)));
+
+    // the releases that arrive late are ignored
+    controls.process(KEYBOARD, key(VirtualKeyCode::Key0, Released));
+    controls.process(MOUSE, button(3, Released));
+    assert_eq!(events(&mut controls), vec![]);
+    controls.process(MOUSE, button(1, Pressed));
+    assert_eq!(events(&mut controls), vec![ControlEvent::Fire(FireTarget::LMBFire)]);
+
+    controls.add_bind(ControlBind::Toggle(HoldableTrigger::KeyCode(VirtualKeyCode::T), SwitchTarget::GHSwitch));
+    controls.process(KEYBOARD, key(VirtualKeyCode::T, Pressed));
+    controls.process(KEYBOARD, key(VirtualKeyCode::T, Released));
+    assert_eq!(events(&mut controls), vec![switch(SwitchTarget::GHSwitch, Active)]);
+    controls.reset_held_state();
+    assert_eq!(events(&mut controls), vec![switch(SwitchTarget::GHSwitch, Inactive)]);
+    assert!(!controls.is_active(SwitchTarget::GHSwitch));
+
+    controls.set_pause_mode(PauseMode::Release);
+    controls.process(KEYBOARD, key(VirtualKeyCode::Key0, Pressed));
+    controls.process(KEYBOARD, key(VirtualKeyCode::T, Pressed));
+    controls.process(KEYBOARD, key(VirtualKeyCode::T, Released));
+    events(&mut controls);
+    controls.pause();
+    let released = events(&mut controls);
+    assert_eq!(released.len(), 2);
+    assert!(released.contains(&switch(SwitchTarget::Key0Switch, Inactive)));
+    assert!(released.contains(&switch(SwitchTarget::GHSwitch, Inactive)));
+    controls.resume();
+    assert_eq!(events(&mut controls), vec![]);
+    assert_eq!(controls.to_toml()["options"]["pause_mode"], toml::Value::String(String::from("Release")));
+}
+
+#[test]
+fn test_bind_while_held() {
+    use crate::ElementState::*;
+    use crate::SwitchState::*;
+
+    let mut controls = create_controls();
+    controls.process(KEYBOARD, key(VirtualKeyCode::B, Pressed));
+    let bind = ControlBind::Switch(HoldableTrigger::KeyCode(VirtualKeyCode::B), SwitchTarget::RMBSwitch);
+    controls.add_bind(bind.clone());
+    assert_eq!(events(&mut controls), vec![switch(SwitchTarget::RMBSwitch, Active)]);
+    controls.remove_bind(bind);
+    assert_eq!(events(&mut controls), vec![switch(SwitchTarget::RMBSwitch, Inactive)]);
+}
+
+#[test]
+fn test_polling() {
+    use crate::ElementState::*;
+
+    let mut controls = create_controls();
+    controls.process(KEYBOARD, key(VirtualKeyCode::G, Pressed));
+    controls.process(MOUSE, button(3, Pressed));
+    assert!(controls.is_active(SwitchTarget::GHSwitch));
+    assert!(!controls.is_active(SwitchTarget::Key0Switch));
+    let mut active: Vec<_> = controls.active_switches().map(|target| target.to_string()).collect();
+    active.sort();
+    assert_eq!(active, vec!["GHSwitch", "RMBSwitch"]);
+    controls.process(KEYBOARD, key(VirtualKeyCode::G, Released));
+    assert!(!controls.is_active(SwitchTarget::GHSwitch));
+
+    controls.add_bind(ControlBind::Value(ValueTrigger::MouseX, ValueTarget::MouseX));
+    controls.add_bind(ControlBind::Value(ValueTrigger::MouseWheel, ValueTarget::MouseX));
+    controls.process(MOUSE, InputEvent::MouseMotion { delta: (1.5, 0.0) });
+    controls.process(MOUSE, InputEvent::MouseMotion { delta: (-0.5, 2.0) });
+    assert_eq!(controls.accumulated_value(ValueTarget::MouseX), 1.0);
+    controls.end_frame();
+    assert_eq!(controls.accumulated_value(ValueTarget::MouseX), 0.0);
+    controls.process(MOUSE, InputEvent::MouseWheel { delta: -1.0 });
+    controls.process(MOUSE, InputEvent::MouseWheel { delta: -1.0 });
+    assert_eq!(controls.accumulated_value(ValueTarget::MouseX), -2.0);
+    controls.end_frame();
+
+    // positions aren't deltas
+    controls.process(MOUSE, InputEvent::Motion { axis: 0, value: 1.5 });
+    controls.process(MOUSE, InputEvent::Motion { axis: 0, value: 1.5 });
+    assert_eq!(controls.accumulated_value(ValueTarget::MouseX), 0.0);
+}
+
+#[test]
+fn test_gamepad() {
+    use crate::ElementState::*;
+    use crate::SwitchState::*;
+
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        GHFire = "GamepadSouth"
+        RMBSwitch = "GamepadLeftTrigger"
+        MouseX = "LeftStickX"
+        [factors]
+    "#).unwrap();
+    let mut controls = TestControls::from_toml(&toml).unwrap();
+    assert_eq!(controls.to_toml(), toml);
+
+    controls.process(GAMEPAD, InputEvent::GamepadButton { button: GamepadButton::South, state: Pressed });
+    controls.process(GAMEPAD, InputEvent::GamepadButton { button: GamepadButton::LeftTrigger, state: Pressed });
+    controls.process(GAMEPAD, InputEvent::GamepadAxis { axis: GamepadAxis::LeftStickX, value: 0.25 });
+    controls.process(GAMEPAD, InputEvent::GamepadAxis { axis: GamepadAxis::LeftStickY, value: 0.5 });
+    controls.process(GAMEPAD, InputEvent::GamepadAxis { axis: GamepadAxis::LeftStickX, value: 0.0 });
+    controls.process(GAMEPAD, InputEvent::GamepadButton { button: GamepadButton::LeftTrigger, state: Released });
+    assert_eq!(events(&mut controls), vec![
+        ControlEvent::Fire(FireTarget::GHFire),
+        switch(SwitchTarget::RMBSwitch, Active),
+        ControlEvent::Value { target: ValueTarget::MouseX, value: 0.25 },
+        ControlEvent::Value { target: ValueTarget::MouseX, value: 0.0 },
+        switch(SwitchTarget::RMBSwitch, Inactive),
+    ]);
+}
+
+#[test]
+fn test_value_processing() {
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        MouseX = 0
+        [factors]
+        [processing.MouseX]
+        deadzone = 0.5
+        curve = 2.0
+        invert = true
+        clamp = [-0.5, 0.5]
+    "#).unwrap();
+    let mut controls = TestControls::from_toml(&toml).unwrap();
+    assert_eq!(controls.to_toml(), toml);
+
+    for &value in [0.25, 0.75, 1.0].iter() {
+        controls.process(GAMEPAD, InputEvent::Motion { axis: 0, value });
+    }
+    let values: Vec<_> = events(&mut controls).into_iter()
+        .map(|event| match event {
+            ControlEvent::Value { value, .. } => value,
+            event => panic!("Unexpected event {:?}", event),
+        })
+        .collect();
+    assert_eq!(values, vec![0.0, -0.25, -0.5]);
+
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        [factors]
+        [processing.MouseX]
+        curve = [[0.0, 0.0], [0.5, 0.1], [0.4, 1.0]]
+    "#).unwrap();
+    let error = TestControls::from_toml(&toml).err().unwrap();
+    assert_eq!(error.path(), "processing.MouseX.curve");
+
+    for &exponent in [0.0, -1.0].iter() {
+        let mut processing = toml::value::Table::new();
+        processing.insert(String::from("curve"), toml::Value::Float(exponent));
+        let error = ValueProcessing::from_toml(&toml::Value::Table(processing)).err().unwrap();
+        assert_eq!(error, ControlsError::InvalidProcessing {
+            path: String::from("curve"),
+            value: toml::Value::Float(exponent),
+        });
+    }
+}
+
+#[test]
+fn test_chords() {
+    use crate::ElementState::*;
+    use crate::SwitchState::*;
+    use crate::VirtualKeyCode::*;
+
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        GHFire = "LControl+G"
+        GHSwitch = "G"
+        RMBSwitch = "LShift+Button3"
+        [factors]
+    "#).unwrap();
+    let mut controls = TestControls::from_toml(&toml).unwrap();
+    assert_eq!(controls.to_toml(), toml);
+
+    controls.process(KEYBOARD, key(LControl, Pressed));
+    controls.process(KEYBOARD, key(G, Pressed));
+    controls.process(KEYBOARD, key(LControl, Released));
+    controls.process(KEYBOARD, key(G, Released));
+    controls.process(KEYBOARD, key(LShift, Pressed));
+    controls.process(MOUSE, button(3, Pressed));
+    controls.process(MOUSE, button(3, Released));
+    controls.process(KEYBOARD, key(LShift, Released));
+    assert_eq!(events(&mut controls), vec![
+        switch(SwitchTarget::GHSwitch, Active),
+        ControlEvent::Fire(FireTarget::GHFire),
+        switch(SwitchTarget::GHSwitch, Inactive),
+        switch(SwitchTarget::RMBSwitch, Active),
+        switch(SwitchTarget::RMBSwitch, Inactive),
+    ]);
+
+    // the order in which the components are pressed doesn't matter
+    controls.process(KEYBOARD, key(G, Pressed));
+    controls.process(KEYBOARD, key(LControl, Pressed));
+    controls.process(KEYBOARD, key(LControl, Released));
+    controls.process(KEYBOARD, key(G, Released));
+    assert_eq!(events(&mut controls), vec![
+        switch(SwitchTarget::GHSwitch, Active),
+        ControlEvent::Fire(FireTarget::GHFire),
+        switch(SwitchTarget::GHSwitch, Inactive),
+    ]);
+
+    controls.set_suppress_chord_components(true);
+    controls.process(KEYBOARD, key(LControl, Pressed));
+    controls.process(KEYBOARD, key(G, Pressed));
+    controls.process(KEYBOARD, key(G, Released));
+    controls.process(KEYBOARD, key(G, Pressed));
+    controls.process(KEYBOARD, key(LControl, Released));
+    controls.process(KEYBOARD, key(G, Released));
+    assert_eq!(events(&mut controls), vec![
+        ControlEvent::Fire(FireTarget::GHFire),
+        ControlEvent::Fire(FireTarget::GHFire),
+    ]);
+    controls.process(KEYBOARD, key(G, Pressed));
+    controls.process(KEYBOARD, key(LControl, Pressed));
+    controls.process(KEYBOARD, key(G, Released));
+    controls.process(KEYBOARD, key(LControl, Released));
+    assert_eq!(events(&mut controls), vec![
+        switch(SwitchTarget::GHSwitch, Active),
+        switch(SwitchTarget::GHSwitch, Inactive),
+        ControlEvent::Fire(FireTarget::GHFire),
+    ]);
+    assert_eq!(controls.to_toml()["options"]["suppress_chord_components"], toml::Value::Boolean(true));
+
+    // a chord needs two different components
+    let g = HoldableTrigger::KeyCode(G);
+    assert_eq!(HoldableTrigger::chord(vec![g.clone(), g.clone()]), None);
+    assert_eq!(HoldableTrigger::chord(vec![]), None);
+    assert!(HoldableTrigger::from_toml(&toml::Value::String(String::from("G+G"))).is_err());
+    let chord = HoldableTrigger::chord(vec![g, HoldableTrigger::Button(1)]).unwrap();
+    assert_eq!(HoldableTrigger::from_toml(&chord.to_toml()).unwrap(), chord);
+}
+
+#[test]
+fn test_sequences() {
+    use std::time::Duration;
+    use crate::ElementState::*;
+    use crate::VirtualKeyCode::*;
+
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        GHFire = { sequence = ["W", "W"], timeout = 250 }
+        LMBFire = { sequence = ["Down", "Right+Down", "X"], timeout = 300 }
+        [factors]
+    "#).unwrap();
+    let mut controls = TestControls::from_toml(&toml).unwrap();
+    assert_eq!(controls.to_toml(), toml);
+
+    let tap = |controls: &mut TestControls, key_code| {
+        controls.process(KEYBOARD, key(key_code, Pressed));
+        controls.tick(Duration::from_millis(50));
+        controls.process(KEYBOARD, key(key_code, Released));
+        controls.tick(Duration::from_millis(100));
+    };
+    tap(&mut controls, W);
+    tap(&mut controls, W);
+    tap(&mut controls, W);
+    assert_eq!(events(&mut controls), vec![ControlEvent::Fire(FireTarget::GHFire)]);
+
+    // too slow
+    controls.tick(Duration::from_millis(300));
+    tap(&mut controls, W);
+    controls.tick(Duration::from_millis(200));
+    tap(&mut controls, W);
+    // interrupted
+    tap(&mut controls, A);
+    tap(&mut controls, W);
+    assert_eq!(events(&mut controls), vec![]);
+
+    controls.process(KEYBOARD, key(Down, Pressed));
+    controls.process(KEYBOARD, key(Right, Pressed));
+    controls.process(KEYBOARD, key(Down, Released));
+    controls.process(KEYBOARD, key(X, Pressed));
+    controls.process(KEYBOARD, key(X, Released));
+    controls.process(KEYBOARD, key(Right, Released));
+    assert_eq!(events(&mut controls), vec![ControlEvent::Fire(FireTarget::LMBFire)]);
+
+    // a mistyped start still counts as far as it matches
+    let steps = vec![HoldableTrigger::KeyCode(A), HoldableTrigger::KeyCode(A), HoldableTrigger::KeyCode(B)];
+    let timeout = Duration::from_millis(300);
+    controls.add_bind(ControlBind::Fire(FireTrigger::Sequence { steps, timeout }, FireTarget::MWUpFire));
+    for &key_code in [A, A, A, B].iter() {
+        tap(&mut controls, key_code);
+    }
+    assert_eq!(events(&mut controls), vec![ControlEvent::Fire(FireTarget::MWUpFire)]);
+
+    // can never fire, so it isn't added
+    let sequence = FireTrigger::Sequence { steps: vec![], timeout };
+    controls.add_bind(ControlBind::Fire(sequence, FireTarget::MWDownFire));
+    tap(&mut controls, A);
+    assert_eq!(events(&mut controls), vec![]);
+    assert_eq!(controls.get_binds().len(), 3);
+
+    // a key press activates its key code and its scan code at once
+    let steps = vec![HoldableTrigger::ScanCode(16), HoldableTrigger::ScanCode(16)];
+    controls.add_bind(ControlBind::Fire(FireTrigger::Sequence { steps, timeout }, FireTarget::MWDownFire));
+    for _ in 0..2 {
+        controls.process(KEYBOARD, InputEvent::Key { scan_code: 16, key_code: Some(Q), state: Pressed });
+        controls.process(KEYBOARD, InputEvent::Key { scan_code: 16, key_code: Some(Q), state: Released });
+    }
+    assert_eq!(events(&mut controls), vec![ControlEvent::Fire(FireTarget::MWDownFire)]);
+
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        GHFire = { sequence = ["W", "Nope"] }
+        [factors]
+    "#).unwrap();
+    let error = TestControls::from_toml(&toml).err().unwrap();
+    assert_eq!(error.path(), "binds.GHFire.sequence[1]");
+}
+
+#[test]
+fn test_tap_and_long_press() {
+    use std::time::Duration;
+    use crate::ElementState::*;
+    use crate::VirtualKeyCode::*;
+
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        GHFire = { tap = "R", threshold = 200 }
+        LMBFire = { long_press = "R", threshold = 500 }
+        MWUpFire = { release = "R" }
+        [factors]
+    "#).unwrap();
+    let mut controls = TestControls::from_toml(&toml).unwrap();
+    assert_eq!(controls.to_toml(), toml);
+
+    controls.process(KEYBOARD, key(R, Pressed));
+    controls.tick(Duration::from_millis(100));
+    controls.process(KEYBOARD, key(R, Released));
+    assert_eq!(events(&mut controls), vec![
+        ControlEvent::Fire(FireTarget::MWUpFire),
+        ControlEvent::Fire(FireTarget::GHFire),
+    ]);
+
+    controls.process(KEYBOARD, key(R, Pressed));
+    controls.tick(Duration::from_millis(300));
+    assert_eq!(events(&mut controls), vec![]);
+    controls.tick(Duration::from_millis(300));
+    assert_eq!(events(&mut controls), vec![ControlEvent::Fire(FireTarget::LMBFire)]);
+    controls.tick(Duration::from_millis(300));
+    controls.process(KEYBOARD, key(R, Released));
+    assert_eq!(events(&mut controls), vec![ControlEvent::Fire(FireTarget::MWUpFire)]);
+
+    // durations finer than a millisecond survive the round trip
+    let g = HoldableTrigger::KeyCode(G);
+    for trigger in [
+        FireTrigger::Tap { trigger: g.clone(), threshold: Duration::from_micros(1500) },
+        FireTrigger::LongPress { trigger: g.clone(), threshold: Duration::from_nanos(250) },
+        FireTrigger::Repeat { trigger: g.clone(), delay: Duration::from_millis(400), interval: Duration::from_micros(16_667) },
+        FireTrigger::Sequence { steps: vec![g.clone(), g.clone()], timeout: Duration::new(1, 1) },
+    ] {
+        let mut table = toml::value::Table::new();
+        table.insert(String::from("trigger"), trigger.to_toml());
+        let text = toml::to_string(&table).unwrap();
+        let value: toml::Value = toml::from_str(&text).unwrap();
+        assert_eq!(FireTrigger::from_toml(&value["trigger"]).unwrap(), trigger);
+    }
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        GHFire = { tap = "R", threshold = 2.5 }
+        LMBFire = { long_press = "R", threshold = -2.5 }
+        [factors]
+    "#).unwrap();
+    let error = TestControls::from_toml(&toml).err().unwrap();
+    assert_eq!(error.path(), "binds.LMBFire.threshold");
+}
+
+#[test]
+fn test_toggle() {
+    use crate::ElementState::*;
+    use crate::SwitchState::*;
+    use crate::VirtualKeyCode::*;
+
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        Key0Switch = { toggle = "C" }
+        [factors]
+    "#).unwrap();
+    let mut controls = TestControls::from_toml(&toml).unwrap();
+    assert_eq!(controls.to_toml(), toml);
+    controls.add_bind(ControlBind::Switch(HoldableTrigger::KeyCode(Key0), SwitchTarget::Key0Switch));
+
+    controls.process(KEYBOARD, key(C, Pressed));
+    controls.process(KEYBOARD, key(C, Released));
+    assert_eq!(events(&mut controls), vec![switch(SwitchTarget::Key0Switch, Active)]);
+    // holding the other bind doesn't end the toggle
+    controls.process(KEYBOARD, key(Key0, Pressed));
+    controls.process(KEYBOARD, key(Key0, Released));
+    assert!(controls.is_active(SwitchTarget::Key0Switch));
+    controls.process(KEYBOARD, key(C, Pressed));
+    controls.process(KEYBOARD, key(C, Released));
+    assert_eq!(events(&mut controls), vec![switch(SwitchTarget::Key0Switch, Inactive)]);
+
+    // and toggling off doesn't end the hold
+    controls.process(KEYBOARD, key(Key0, Pressed));
+    controls.process(KEYBOARD, key(C, Pressed));
+    controls.process(KEYBOARD, key(C, Released));
+    controls.process(KEYBOARD, key(C, Pressed));
+    controls.process(KEYBOARD, key(C, Released));
+    assert_eq!(events(&mut controls), vec![switch(SwitchTarget::Key0Switch, Active)]);
+    controls.process(KEYBOARD, key(Key0, Released));
+    assert_eq!(events(&mut controls), vec![switch(SwitchTarget::Key0Switch, Inactive)]);
+
+    // removing a toggled on bind turns it off
+    controls.process(KEYBOARD, key(C, Pressed));
+    controls.remove_bind(ControlBind::Toggle(HoldableTrigger::KeyCode(C), SwitchTarget::Key0Switch));
+    assert_eq!(events(&mut controls), vec![
+        switch(SwitchTarget::Key0Switch, Active),
+        switch(SwitchTarget::Key0Switch, Inactive),
+    ]);
+}
+
+#[test]
+fn test_repeat() {
+    use std::time::Duration;
+    use crate::ElementState::*;
+    use crate::VirtualKeyCode::*;
+
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        GHFire = { repeat = "Down", delay = 400, interval = 100 }
+        [factors]
+    "#).unwrap();
+    let mut controls = TestControls::from_toml(&toml).unwrap();
+    assert_eq!(controls.to_toml(), toml);
+
+    let fired = |controls: &mut TestControls| events(controls).into_iter()
+        .filter(|event| *event == ControlEvent::Fire(FireTarget::GHFire))
+        .count();
+    controls.process(KEYBOARD, key(Down, Pressed));
+    assert_eq!(fired(&mut controls), 1);
+    controls.tick(Duration::from_millis(350));
+    assert_eq!(fired(&mut controls), 0);
+    controls.tick(Duration::from_millis(50));
+    assert_eq!(fired(&mut controls), 1);
+    controls.tick(Duration::from_millis(250));
+    assert_eq!(fired(&mut controls), 2);
+    controls.process(KEYBOARD, key(Down, Released));
+    controls.tick(Duration::from_millis(1000));
+    assert_eq!(fired(&mut controls), 0);
+
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        GHFire = { repeat = "Down", interval = 0 }
+        [factors]
+    "#).unwrap();
+    let error = TestControls::from_toml(&toml).err().unwrap();
+    assert_eq!(error.path(), "binds.GHFire.interval");
+}
+
+#[test]
+fn test_composite_axis() {
+    use std::time::Duration;
+    use crate::ElementState::*;
+    use crate::VirtualKeyCode::*;
+
+    let value = |value| ControlEvent::Value { target: ValueTarget::MouseX, value };
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        MouseX = { negative = "A", positive = "D" }
+        [factors]
+    "#).unwrap();
+    let mut controls = TestControls::from_toml(&toml).unwrap();
+    assert_eq!(controls.to_toml(), toml);
+
+    controls.process(KEYBOARD, key(D, Pressed));
+    controls.process(KEYBOARD, key(A, Pressed));
+    controls.process(KEYBOARD, key(D, Released));
+    controls.process(KEYBOARD, key(A, Released));
+    assert_eq!(events(&mut controls), vec![value(1.0), value(0.0), value(-1.0), value(0.0)]);
+
+    // the changes that couldn't be announced are announced on resume and enable
+    controls.process(KEYBOARD, key(D, Pressed));
+    controls.pause();
+    controls.process(KEYBOARD, key(D, Released));
+    controls.resume();
+    assert_eq!(events(&mut controls), vec![value(1.0), value(0.0)]);
+    controls.process(KEYBOARD, key(D, Pressed));
+    controls.disable_target(Target::Value(ValueTarget::MouseX));
+    controls.process(KEYBOARD, key(D, Released));
+    controls.enable_target(Target::Value(ValueTarget::MouseX));
+    assert_eq!(events(&mut controls), vec![value(1.0), value(0.0)]);
+
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        MouseX = { negative = "A", positive = "D", ramp_up = 100, ramp_down = 50 }
+        [factors]
+    "#).unwrap();
+    let mut controls = TestControls::from_toml(&toml).unwrap();
+    assert_eq!(controls.to_toml(), toml);
+
+    controls.process(KEYBOARD, key(D, Pressed));
+    assert_eq!(events(&mut controls), vec![]);
+    controls.tick(Duration::from_millis(50));
+    controls.tick(Duration::from_millis(100));
+    controls.tick(Duration::from_millis(100));
+    controls.process(KEYBOARD, key(D, Released));
+    controls.tick(Duration::from_millis(25));
+    controls.tick(Duration::from_millis(25));
+    controls.tick(Duration::from_millis(25));
+    assert_eq!(events(&mut controls), vec![value(0.5), value(1.0), value(0.5), value(0.0)]);
+
+    // forgetting the held keys skips the ramp
+    controls.set_pause_mode(PauseMode::Release);
+    controls.process(KEYBOARD, key(A, Pressed));
+    controls.tick(Duration::from_millis(100));
+    controls.pause();
+    controls.resume();
+    controls.tick(Duration::from_millis(100));
+    assert_eq!(events(&mut controls), vec![value(-1.0), value(0.0)]);
+}
+
+#[test]
+fn test_axis_threshold() {
+    use crate::SwitchState::*;
+
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        GHFire = { axis = 2, threshold = 0.5, hysteresis = 0.125 }
+        GHSwitch = { axis = 2, threshold = 0.5, hysteresis = 0.125 }
+        RMBSwitch = { axis = 2, threshold = -0.5 }
+        [factors]
+    "#).unwrap();
+    let mut controls = TestControls::from_toml(&toml).unwrap();
+    assert_eq!(controls.to_toml(), toml);
+
+    for &value in [0.25, 0.75, 0.4, 0.6, 0.3, -0.25, -0.75, 0.0].iter() {
+        controls.process(GAMEPAD, InputEvent::Motion { axis: 2, value });
+    }
+    assert_eq!(events(&mut controls), vec![
+        ControlEvent::Fire(FireTarget::GHFire),
+        switch(SwitchTarget::GHSwitch, Active),
+        switch(SwitchTarget::GHSwitch, Inactive),
+        switch(SwitchTarget::RMBSwitch, Active),
+        switch(SwitchTarget::RMBSwitch, Inactive),
+    ]);
+
+    // counted per device, like keys
+    controls.process(GAMEPAD, InputEvent::Motion { axis: 2, value: 1.0 });
+    controls.process(OTHER_MOUSE, InputEvent::Motion { axis: 2, value: 1.0 });
+    controls.process(GAMEPAD, InputEvent::Motion { axis: 2, value: 0.0 });
+    assert!(controls.is_active(SwitchTarget::GHSwitch));
+    controls.process(OTHER_MOUSE, InputEvent::Motion { axis: 2, value: 0.0 });
+    assert!(!controls.is_active(SwitchTarget::GHSwitch));
+
+    // in chords they are written as text, gamepad axes work too
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        GHFire = "LShift+Axis2>0.5~0.125"
+        RMBSwitch = { axis = "LeftTrigger", threshold = 0.5 }
+        AMMBSwitch = { axis = "LeftStickY", threshold = -0.25 }
+        [factors]
+    "#).unwrap();
+    let mut controls = TestControls::from_toml(&toml).unwrap();
+    assert_eq!(controls.to_toml(), toml);
+    let text = toml::to_string(&toml).unwrap();
+    assert_eq!(TestControls::from_toml(&toml::from_str(&text).unwrap()).unwrap().to_toml(), toml);
+
+    controls.process(KEYBOARD, key(VirtualKeyCode::LShift, ElementState::Pressed));
+    controls.process(GAMEPAD, InputEvent::Motion { axis: 2, value: 0.75 });
+    controls.process(GAMEPAD, InputEvent::GamepadAxis { axis: GamepadAxis::LeftTrigger, value: 1.0 });
+    controls.process(GAMEPAD, InputEvent::GamepadAxis { axis: GamepadAxis::LeftStickY, value: -0.5 });
+    assert_eq!(events(&mut controls), vec![
+        ControlEvent::Fire(FireTarget::GHFire),
+        switch(SwitchTarget::RMBSwitch, Active),
+        switch(SwitchTarget::AMMBSwitch, Active),
+    ]);
+
+    let name = toml::Value::String(String::from("LeftStickY<-0.25"));
+    assert_eq!(HoldableTrigger::from_toml(&name), HoldableTrigger::from_toml(&toml["binds"]["AMMBSwitch"]));
+    for name in ["Axis2>0", "Axis2<0.5", "LeftStickY>0.5~-0.1", "NoAxis>0.5"].iter() {
+        assert!(HoldableTrigger::from_toml(&toml::Value::String(String::from(*name))).is_err());
+    }
+}
+
+#[test]
+fn test_vectors() {
+    use crate::ElementState::*;
+    use crate::VirtualKeyCode::*;
+
+    let vector = |x, y| ControlEvent::Vector { target: ValueTarget::MouseX, value: (x, y) };
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        MouseX = { up = "W", down = "S", left = "A", right = "D" }
+        [factors]
+    "#).unwrap();
+    let mut controls = TestControls::from_toml(&toml).unwrap();
+    assert_eq!(controls.to_toml(), toml);
+
+    controls.process(KEYBOARD, key(W, Pressed));
+    controls.process(KEYBOARD, key(D, Pressed));
+    controls.process(KEYBOARD, key(W, Released));
+    controls.process(KEYBOARD, key(D, Released));
+    let diagonal = 1.0 / 2f64.sqrt();
+    assert_eq!(events(&mut controls), vec![
+        vector(0.0, 1.0),
+        vector(diagonal, diagonal),
+        vector(1.0, 0.0),
+        vector(0.0, 0.0),
+    ]);
+
+    controls.process(KEYBOARD, key(W, Pressed));
+    controls.pause();
+    controls.process(KEYBOARD, key(W, Released));
+    controls.resume();
+    assert_eq!(events(&mut controls), vec![vector(0.0, 1.0), vector(0.0, 0.0)]);
+    controls.process(KEYBOARD, key(S, Pressed));
+    controls.reset_held_state();
+    assert_eq!(events(&mut controls), vec![vector(0.0, -1.0), vector(0.0, 0.0)]);
+
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        MouseX = "LeftStick"
+        [factors]
+        MouseX = 2.0
+        [processing.MouseX]
+        radial_deadzone = 0.5
+    "#).unwrap();
+    let mut controls = TestControls::from_toml(&toml).unwrap();
+    assert_eq!(controls.to_toml(), toml);
+
+    for &(axis, value) in [
+        (GamepadAxis::LeftStickX, 0.3),
+        (GamepadAxis::LeftStickY, 0.4),
+        (GamepadAxis::LeftStickX, 0.0),
+        (GamepadAxis::LeftStickY, 1.0),
+        (GamepadAxis::LeftStickY, 0.75),
+    ].iter() {
+        controls.process(GAMEPAD, InputEvent::GamepadAxis { axis, value });
+    }
+    assert_eq!(events(&mut controls), vec![
+        vector(0.0, 0.0),
+        vector(0.0, 0.0),
+        vector(0.0, 0.0),
+        vector(0.0, 2.0),
+        vector(0.0, 1.0),
+    ]);
+    // stick positions aren't summed up, mouse motion is
+    assert_eq!(controls.accumulated_vector(ValueTarget::MouseX), (0.0, 0.0));
+    let mut controls = TestControls::new();
+    controls.add_bind(ControlBind::Vector(VectorTrigger::Mouse, ValueTarget::MouseX));
+    controls.process(MOUSE, InputEvent::MouseMotion { delta: (1.0, 2.0) });
+    controls.process(MOUSE, InputEvent::MouseMotion { delta: (0.5, -1.0) });
+    assert_eq!(controls.accumulated_vector(ValueTarget::MouseX), (1.5, 1.0));
+
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        MouseX = { up = "W", down = "S", left = "A", right = "Nope" }
+        [factors]
+    "#).unwrap();
+    let error = TestControls::from_toml(&toml).err().unwrap();
+    assert_eq!(error.path(), "binds.MouseX.right");
+}
+
+#[test]
+fn test_contexts() {
+    use crate::ElementState::*;
+    use crate::SwitchState::*;
+    use crate::VirtualKeyCode::*;
+
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        RMBSwitch = "W"
+        GHFire = "Return"
+        [factors]
+        [contexts.Menu]
+        consume = true
+        [contexts.Menu.binds]
+        LMBFire = "W"
+        [contexts.Chat.binds]
+        GHSwitch = "Return"
+    "#).unwrap();
+    let mut controls = TestControls::from_toml(&toml).unwrap();
+    assert_eq!(controls.to_toml(), toml);
+
+    controls.process(KEYBOARD, key(W, Pressed));
+    controls.push_context("Menu");
+    controls.process(KEYBOARD, key(W, Released));
+    controls.process(KEYBOARD, key(W, Pressed));
+    controls.process(KEYBOARD, key(W, Released));
+    assert_eq!(events(&mut controls), vec![
+        switch(SwitchTarget::RMBSwitch, Active),
+        switch(SwitchTarget::RMBSwitch, Inactive),
+        ControlEvent::Fire(FireTarget::LMBFire),
+    ]);
+    assert_eq!(controls.get_binds().len(), 2);
+
+    // doesn't consume, so both binds of Return are in effect
+    controls.push_context("Chat");
+    controls.process(KEYBOARD, key(Return, Pressed));
+    controls.pop_context();
+    assert_eq!(events(&mut controls), vec![
+        ControlEvent::Fire(FireTarget::GHFire),
+        switch(SwitchTarget::GHSwitch, Active),
+        switch(SwitchTarget::GHSwitch, Inactive),
+    ]);
+    assert_eq!(controls.context_stack(), ["Menu"]);
+
+    controls.pop_context();
+    controls.process(KEYBOARD, key(W, Pressed));
+    assert_eq!(events(&mut controls), vec![switch(SwitchTarget::RMBSwitch, Active)]);
+    // the contexts are not part of the base binds
+    assert_eq!(controls.to_toml(), toml);
+
+    // consuming a key also hides the binds that only use it as a part
+    let toml: toml::Value = toml::from_str(r#"
+        [binds]
+        MouseX = { negative = "A", positive = "D" }
+        GHFire = "LControl+A"
+        [factors]
+        [contexts.Menu]
+        consume = true
+        [contexts.Menu.binds]
+        LMBFire = "A"
+    "#).unwrap();
+    let mut controls = TestControls::from_toml(&toml).unwrap();
+    controls.push_context("Menu");
+    controls.process(KEYBOARD, key(LControl, Pressed));
+    controls.process(KEYBOARD, key(A, Pressed));
+    assert_eq!(events(&mut controls), vec![ControlEvent::Fire(FireTarget::LMBFire)]);
+    assert_eq!(controls.get_binds().len(), 1);
+}
+
+#[test]
+fn test_disabled_targets() {
+    use crate::ElementState::*;
+    use crate::SwitchState::*;
+    use crate::VirtualKeyCode::*;
+
+    let mut controls = create_controls();
+    controls.process(KEYBOARD, key(Key0, Pressed));
+    controls.disable_targets(vec![
+        Target::Fire(FireTarget::GHFire),
+        Target::Switch(SwitchTarget::GHSwitch),
+        Target::Switch(SwitchTarget::Key0Switch),
+        Target::Value(ValueTarget::MouseX),
+    ]);
+    controls.process(KEYBOARD, key(G, Pressed));
+    controls.process(MOUSE, button(1, Pressed));
+    controls.process(MOUSE, InputEvent::Motion { axis: 0, value: 1.0 });
+    assert_eq!(events(&mut controls), vec![
+        switch(SwitchTarget::Key0Switch, Active),
+        switch(SwitchTarget::Key0Switch, Inactive),
+        ControlEvent::Fire(FireTarget::LMBFire),
+    ]);
+    assert!(!controls.is_target_enabled(Target::Switch(SwitchTarget::GHSwitch)));
+    assert!(!controls.is_active(SwitchTarget::GHSwitch));
+
+    // re-announced like on resume
+    controls.process(KEYBOARD, key(Key0, Released));
+    controls.enable_target(Target::Switch(SwitchTarget::Key0Switch));
+    controls.enable_target(Target::Switch(SwitchTarget::GHSwitch));
+    assert_eq!(events(&mut controls), vec![switch(SwitchTarget::GHSwitch, Active)]);
+}
+
+#[test]
+fn test_capture() {
+    use crate::ElementState::*;
+    use crate::SwitchState::*;
+    use crate::VirtualKeyCode::*;
+
+    let mut controls = create_controls();
+    controls.add_bind(ControlBind::Switch(HoldableTrigger::KeyCode(Escape), SwitchTarget::RMBSwitch));
+    controls.process(KEYBOARD, key(A, Pressed));
+    controls.start_capture(CaptureOptions {
+        ignore: vec![HoldableTrigger::KeyCode(Escape)],
+        ..CaptureOptions::default()
+    });
+    // released triggers and ignored ones are dispatched as usual
+    controls.process(KEYBOARD, key(A, Released));
+    controls.process(KEYBOARD, key(Escape, Pressed));
+    controls.process(KEYBOARD, key(Escape, Released));
+    controls.process(KEYBOARD, key(LControl, Pressed));
+    controls.process(KEYBOARD, key(G, Pressed));
+    assert!(controls.is_capturing());
+    assert_eq!(controls.take_captured_trigger(), None);
+    controls.process(KEYBOARD, key(G, Released));
+    controls.process(KEYBOARD, key(LControl, Released));
+    assert!(!controls.is_capturing());
+    let trigger = controls.take_captured_trigger().unwrap();
+    assert_eq!(trigger, CapturedTrigger::Holdable(HoldableTrigger::chord(vec![
+        HoldableTrigger::KeyCode(LControl),
+        HoldableTrigger::KeyCode(G),
+    ]).unwrap()));
+    assert_eq!(events(&mut controls), vec![
+        switch(SwitchTarget::AMMBSwitch, Active),
+        switch(SwitchTarget::AMMBSwitch, Inactive),
+        switch(SwitchTarget::RMBSwitch, Active),
+        switch(SwitchTarget::RMBSwitch, Inactive),
+    ]);
+
+    controls.add_bind(trigger.to_bind(Target::Fire(FireTarget::LMBFire)).unwrap());
+    controls.process(KEYBOARD, key(LControl, Pressed));
+    controls.process(KEYBOARD, key(G, Pressed));
+    assert!(events(&mut controls).contains(&ControlEvent::Fire(FireTarget::LMBFire)));
+
+    // the axes a mouse reports while it moves are ignored, unless raw axes are asked for
+    controls.start_capture(CaptureOptions { kind: Some(TargetKind::Fire), ..CaptureOptions::default() });
+    controls.process(MOUSE, InputEvent::Motion { axis: 0, value: 0.5 });
+    controls.process(MOUSE, InputEvent::Motion { axis: 1, value: -3.0 });
+    controls.process(MOUSE, InputEvent::MouseMotion { delta: (0.5, -3.0) });
+    assert!(controls.is_capturing());
+    controls.process(MOUSE, button(4, Pressed));
+    controls.process(MOUSE, button(4, Released));
+    assert_eq!(controls.take_captured_trigger(), Some(CapturedTrigger::Holdable(HoldableTrigger::Button(4))));
+
+    // keys can't drive a value target, axes below the threshold don't count
+    controls.start_capture(CaptureOptions {
+        kind: Some(TargetKind::Value),
+        raw_axes: true,
+        ..CaptureOptions::default()
+    });
+    controls.process(KEYBOARD, key(H, Pressed));
+    controls.process(KEYBOARD, key(H, Released));
+    controls.process(GAMEPAD, InputEvent::Motion { axis: 3, value: 0.25 });
+    controls.process(GAMEPAD, InputEvent::Motion { axis: 3, value: -0.75 });
+    assert_eq!(controls.take_captured_trigger(), Some(CapturedTrigger::Value(ValueTrigger::Axis(3))));
+
+    controls.start_capture(CaptureOptions {
+        kind: Some(TargetKind::Switch),
+        raw_axes: true,
+        ..CaptureOptions::default()
+    });
+    controls.process(GAMEPAD, InputEvent::Motion { axis: 3, value: -0.75 });
+    let trigger = controls.take_captured_trigger().unwrap();
+    assert_eq!(trigger.clone().to_bind(TestTarget::Value(ValueTarget::MouseX)), None);
+    assert_eq!(trigger, CapturedTrigger::Holdable(HoldableTrigger::AxisThreshold(
+        AxisThreshold { axis: ThresholdAxis::Axis(3), threshold: -0.5, hysteresis: 0.0 },
+    )));
+
+    controls.start_capture(CaptureOptions { kind: Some(TargetKind::Fire), ..CaptureOptions::default() });
+    controls.process(GAMEPAD, InputEvent::GamepadAxis { axis: GamepadAxis::LeftTrigger, value: 0.75 });
+    assert_eq!(controls.take_captured_trigger(), Some(CapturedTrigger::Holdable(HoldableTrigger::AxisThreshold(
+        AxisThreshold { axis: ThresholdAxis::GamepadAxis(GamepadAxis::LeftTrigger), threshold: 0.5, hysteresis: 0.0 },
+    ))));
+}
+
+#[test]
+fn test_scoped_binds() {
+    use crate::ElementState::*;
+    use crate::VirtualKeyCode::*;
+
+    let mut controls = TestControls::new();
+    controls.set_auto_assign_slots(2);
+    controls.add_scoped_bind(BindScope::Slot(0), ControlBind::Fire(
+        FireTrigger::Holdable(HoldableTrigger::KeyCode(G)),
+        FireTarget::GHFire,
+    ));
+    controls.add_scoped_bind(BindScope::Slot(1), ControlBind::Fire(
+        FireTrigger::Holdable(HoldableTrigger::KeyCode(G)),
+        FireTarget::LMBFire,
+    ));
+    controls.add_scoped_bind(BindScope::Device(GAMEPAD), ControlBind::Switch(
+        HoldableTrigger::GamepadButton(GamepadButton::South),
+        SwitchTarget::GHSwitch,
+    ));
+
+    controls.process(KEYBOARD, key(G, Pressed));
+    assert_eq!(events(&mut controls), vec![
+        ControlEvent::DeviceAssigned { device_id: KEYBOARD, slot: 0 },
+        ControlEvent::Fire(FireTarget::GHFire),
+    ]);
+    controls.process(OTHER_KEYBOARD, key(G, Pressed));
+    assert_eq!(events(&mut controls), vec![
+        ControlEvent::DeviceAssigned { device_id: OTHER_KEYBOARD, slot: 1 },
+        ControlEvent::Fire(FireTarget::LMBFire),
+    ]);
+    controls.process(KEYBOARD, key(G, Released));
+    controls.process(KEYBOARD, key(G, Pressed));
+    assert_eq!(events(&mut controls), vec![ControlEvent::Fire(FireTarget::GHFire)]);
+
+    // both slots are taken
+    let south = |state| InputEvent::GamepadButton { button: GamepadButton::South, state };
+    controls.process(GAMEPAD, south(Pressed));
+    assert_eq!(events(&mut controls), vec![switch(SwitchTarget::GHSwitch, SwitchState::Active)]);
+    assert_eq!(controls.device_slot(GAMEPAD), None);
+    controls.process(OTHER_GAMEPAD, south(Pressed));
+    assert_eq!(events(&mut controls), vec![]);
+
+    controls.process(OTHER_KEYBOARD, InputEvent::Removed);
+    assert_eq!(events(&mut controls), vec![
+        ControlEvent::DeviceDisconnected(OTHER_KEYBOARD),
+        ControlEvent::DeviceUnassigned { device_id: OTHER_KEYBOARD, slot: 1 },
+    ]);
+    assert_eq!(controls.slot_devices(1).count(), 0);
+    controls.process(OTHER_GAMEPAD, south(Released));
+    controls.process(OTHER_GAMEPAD, south(Pressed));
+    assert_eq!(events(&mut controls), vec![ControlEvent::DeviceAssigned { device_id: OTHER_GAMEPAD, slot: 1 }]);
+
+    // leaving a slot releases what the device held in it
+    controls.add_scoped_bind(BindScope::Slot(0), ControlBind::Switch(HoldableTrigger::Button(3), SwitchTarget::RMBSwitch));
+    controls.assign_device(MOUSE, 0);
+    controls.process(MOUSE, button(3, Pressed));
+    assert_eq!(events(&mut controls), vec![switch(SwitchTarget::RMBSwitch, SwitchState::Active)]);
+    assert_eq!(controls.unassign_device(MOUSE), Some(0));
+    assert_eq!(events(&mut controls), vec![switch(SwitchTarget::RMBSwitch, SwitchState::Inactive)]);
+    controls.process(MOUSE, button(3, Released));
+    assert_eq!(events(&mut controls), vec![]);
+    controls.assign_device(MOUSE, 0);
+    controls.process(MOUSE, button(3, Pressed));
+    controls.assign_device(MOUSE, 1);
+    controls.process(MOUSE, button(3, Released));
+    assert_eq!(events(&mut controls), vec![
+        switch(SwitchTarget::RMBSwitch, SwitchState::Active),
+        switch(SwitchTarget::RMBSwitch, SwitchState::Inactive),
+    ]);
+    controls.unassign_device(MOUSE);
+
+    let toml = controls.to_toml();
+    assert_eq!(toml["slots"]["1"]["LMBFire"], toml::Value::String(String::from("G")));
+    assert_eq!(toml["options"]["auto_assign_slots"], toml::Value::Integer(2));
+    let parsed = TestControls::from_toml(&toml).unwrap();
+    assert_eq!(parsed.get_scoped_binds(BindScope::Slot(0)), controls.get_scoped_binds(BindScope::Slot(0)));
+    assert_eq!(parsed.to_toml(), toml);
+}
+
+#[test]
+fn test_recording() {
+    use std::time::Duration;
+    use crate::ElementState::*;
+    use crate::VirtualKeyCode::*;
+
+    let create = || {
+        let mut controls = create_controls();
+        controls.add_bind(ControlBind::Fire(
+            FireTrigger::LongPress { trigger: HoldableTrigger::KeyCode(Key0), threshold: Duration::from_millis(300) },
+            FireTarget::GHFire,
+        ));
+        controls
+    };
+    let mut controls = create();
+    controls.start_recording();
+    controls.process(KEYBOARD, key(G, Pressed));
+    controls.process(KEYBOARD, key(Key0, Pressed));
+    controls.tick(Duration::from_millis(200));
+    controls.process(MOUSE, button(3, Pressed));
+    controls.process(MOUSE, InputEvent::MouseWheel { delta: -1.0 });
+    controls.process(GAMEPAD, InputEvent::Motion { axis: 0, value: 0.1 + 0.2 });
+    controls.process(GAMEPAD, InputEvent::GamepadButton { button: GamepadButton::South, state: Pressed });
+    controls.tick(Duration::from_millis(200));
+    controls.process(KEYBOARD, InputEvent::Key { scan_code: 57, key_code: None, state: Pressed });
+    controls.process(KEYBOARD, key(G, Released));
+    controls.process(MOUSE, InputEvent::Removed);
+    // the capture swallows the click, the replay must not dispatch it
+    controls.start_capture(CaptureOptions::default());
+    controls.process(MOUSE, button(1, Pressed));
+    controls.process(MOUSE, button(1, Released));
+    assert!(controls.take_captured_trigger().is_some());
+    controls.process(KEYBOARD, key(H, Pressed));
+    controls.disable_target(Target::Switch(SwitchTarget::GHSwitch));
+    controls.pause();
+    controls.process(KEYBOARD, key(H, Released));
+    controls.resume();
+    controls.enable_target(Target::Switch(SwitchTarget::GHSwitch));
+    controls.push_context("Menu");
+    controls.pop_context();
+    controls.reset_held_state();
+    controls.process(KEYBOARD, key(H, Pressed));
+    let recording = controls.stop_recording().unwrap();
+    assert!(!controls.is_recording());
+    let recorded = events(&mut controls);
+
+    let mut bytes = Vec::new();
+    recording.write_to(&mut bytes).unwrap();
+    let loaded = Recording::read_from(&bytes[..]).unwrap();
+    assert_eq!(loaded, recording);
+    assert_eq!(loaded.to_bytes(), bytes);
+
+    assert!(!recording.inputs().contains(&RecordedInput::Event { time: Duration::from_millis(400), device_id: MOUSE, event: button(1, Pressed) }));
+    assert!(recording.inputs().contains(&RecordedInput::DisableTarget(String::from("GHSwitch"))));
+
+    // replaying records the same inputs again
+    let mut replayed = create();
+    replayed.start_recording();
+    loaded.replay(&mut replayed).unwrap();
+    assert_eq!(replayed.stop_recording().unwrap().to_bytes(), bytes);
+    assert_eq!(events(&mut replayed), recorded);
+    assert_eq!(replayed.context_stack(), controls.context_stack());
+
+    assert!(Recording::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    assert!(Recording::from_bytes(b"nothing").is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde() {
+    let trigger = HoldableTrigger::chord(vec![HoldableTrigger::KeyCode(VirtualKeyCode::LControl), HoldableTrigger::Button(1)]).unwrap();
+    let json = serde_json::to_string(&FireTrigger::Holdable(trigger.clone())).unwrap();
+    assert_eq!(json, r#""LControl+Button1""#);
+    assert_eq!(serde_json::from_str::<FireTrigger>(&json).unwrap(), FireTrigger::Holdable(trigger.clone()));
+
+    let direction: MouseWheelDirection = serde_json::from_str(r#""Down""#).unwrap();
+    assert_eq!(direction, MouseWheelDirection::Down);
+    assert!(serde_json::from_str::<HoldableTrigger>(r#""NoSuchKey""#).is_err());
+
+    let bind = ControlBind::Value(ValueTrigger::Axis(2), ValueTarget::MouseX);
+    let json = serde_json::to_string(&bind).unwrap();
+    assert_eq!(json, r#"{"MouseX":2}"#);
+    assert_eq!(serde_json::from_str::<ControlBind<FireTarget, SwitchTarget, ValueTarget>>(&json).unwrap(), bind);
+    let error = serde_json::from_str::<ControlBind<FireTarget, SwitchTarget, ValueTarget>>("{}").unwrap_err();
+    assert!(error.to_string().starts_with("invalid length 0"));
+
+    let controls = create_controls();
+    let json = serde_json::to_string(&controls.snapshot()).unwrap();
+    let parsed = TestControls::from_snapshot(&serde_json::from_str(&json).unwrap()).unwrap();
+    assert_eq!(parsed.get_binds().len(), controls.get_binds().len());
+    assert_eq!(parsed.to_toml(), controls.to_toml());
+
+    // bincode isn't self-describing
+    let bytes = bincode::serialize(&bind).unwrap();
+    assert_eq!(bincode::deserialize::<ControlBind<FireTarget, SwitchTarget, ValueTarget>>(&bytes).unwrap(), bind);
+    let bytes = bincode::serialize(&FireTrigger::Holdable(trigger.clone())).unwrap();
+    assert_eq!(bincode::deserialize::<FireTrigger>(&bytes).unwrap(), FireTrigger::Holdable(trigger));
+    let bytes = bincode::serialize(&controls.snapshot()).unwrap();
+    let parsed = TestControls::from_snapshot(&bincode::deserialize(&bytes).unwrap()).unwrap();
+    assert_eq!(parsed.to_toml(), controls.to_toml());
+}
+
+#[cfg(feature = "gilrs")]
+#[test]
+fn test_gilrs_events() {
+    use gilrs::Axis;
+    use gilrs::Button;
+    use gilrs::EventType;
+    use crate::ElementState::*;
+
+    // the code is platform specific and isn't looked at
+    let code = Button::South.to_nec().unwrap();
+    let gamepad_button = |button, state| Some(InputEvent::GamepadButton { button, state });
+    let gamepad_axis = |axis, value| Some(InputEvent::GamepadAxis { axis, value });
+    for &(event_type, expected) in [
+        (EventType::ButtonPressed(Button::South, code), gamepad_button(GamepadButton::South, Pressed)),
+        (EventType::ButtonReleased(Button::DPadLeft, code), gamepad_button(GamepadButton::DPadLeft, Released)),
+        (EventType::ButtonPressed(Button::LeftTrigger, code), gamepad_button(GamepadButton::LeftBumper, Pressed)),
+        (EventType::ButtonPressed(Button::LeftTrigger2, code), gamepad_button(GamepadButton::LeftTrigger, Pressed)),
+        (EventType::ButtonReleased(Button::RightTrigger2, code), gamepad_button(GamepadButton::RightTrigger, Released)),
+        (EventType::ButtonPressed(Button::C, code), None),
+        // the analog triggers report their position as button value
+        (EventType::ButtonChanged(Button::LeftTrigger2, 0.5, code), gamepad_axis(GamepadAxis::LeftTrigger, 0.5)),
+        (EventType::ButtonChanged(Button::RightTrigger2, 0.25, code), gamepad_axis(GamepadAxis::RightTrigger, 0.25)),
+        (EventType::ButtonChanged(Button::South, 1.0, code), None),
+        (EventType::AxisChanged(Axis::LeftStickY, -0.5, code), gamepad_axis(GamepadAxis::LeftStickY, -0.5)),
+        (EventType::AxisChanged(Axis::RightStickX, 0.75, code), gamepad_axis(GamepadAxis::RightStickX, 0.75)),
+        (EventType::AxisChanged(Axis::LeftZ, 0.5, code), None),
+        (EventType::Disconnected, Some(InputEvent::Removed)),
+        (EventType::Connected, None),
+    ].iter() {
+        assert_eq!(InputEvent::from_gilrs(event_type), expected);
+    }
+}
+
+// needs a display and a human pressing keys, run with `cargo test -- --ignored`
+#[cfg(feature = "winit")]
+#[test]
+#[ignore]
+fn test_all() {
+    use winit::event_loop::EventLoop;
+    use winit::event::Event;
+    use winit::event::WindowEvent;
+    use winit::window::Window;
+    use winit::event_loop::ControlFlow;
+    use std::collections::vec_deque::VecDeque;
+
+    let event_loop = EventLoop::new();
+    let _window = Window::new(&event_loop).unwrap();
+    let mut controls = create_controls();
+
+    let mut close_requested = false;
+    let mut event_buffer = VecDeque::new();
+    event_loop.run(move |event, _, control_flow| {
+        //eprintln!("{:?}", event);
+        match event {
+            Event::DeviceEvent { device_id, event } => controls.process_winit(device_id, event),
+            Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => close_requested = true,
+            Event::WindowEvent { event: WindowEvent::Focused(focused), .. } => {
+                if focused {
+                    controls.resume();
+                } else {
+                    controls.pause()
+                }
+            },
+            _ => (),
+        }
+
+        controls.get_events(&mut event_buffer);
+        for event in event_buffer.drain(..) {
+            eprintln!("{:?}", event);
+        }
+
+        if close_requested {
+            *control_flow = ControlFlow::Exit;
+        } else {
+            *control_flow = ControlFlow::Wait;
+        }
+    });
+}
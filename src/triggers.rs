@@ -1,138 +1,483 @@
 use std::convert::AsRef;
+use std::collections::BTreeSet;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use std::cmp::Ordering;
+use std::hash::Hash;
+use std::hash::Hasher;
 
-use toml;
 use num::NumCast;
-use winit::event::VirtualKeyCode;
 
 use super::MouseWheelDirection;
+use super::VirtualKeyCode;
+use super::keys::KEY_CODE_PAIRS;
+use super::GamepadAxis;
+use super::GamepadButton;
+use super::gamepad::GAMEPAD_AXIS_PAIRS;
+use super::gamepad::GAMEPAD_BUTTON_PAIRS;
+use super::ControlsError;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+const DEFAULT_SEQUENCE_TIMEOUT: Duration = Duration::from_millis(300);
+const DEFAULT_TAP_THRESHOLD: Duration = Duration::from_millis(200);
+const DEFAULT_LONG_PRESS_THRESHOLD: Duration = Duration::from_millis(500);
+const DEFAULT_REPEAT_DELAY: Duration = Duration::from_millis(500);
+const DEFAULT_REPEAT_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum FireTrigger {
     Holdable(HoldableTrigger),
     MouseWheelTick(MouseWheelDirection),
+    // fires when the steps are pressed in order, with at most `timeout` between two steps
+    Sequence { steps: Vec<HoldableTrigger>, timeout: Duration },
+    Release(HoldableTrigger),
+    // fires on release if the trigger was held shorter than `threshold`
+    Tap { trigger: HoldableTrigger, threshold: Duration },
+    // fires as soon as the trigger has been held for `threshold`
+    LongPress { trigger: HoldableTrigger, threshold: Duration },
+    // fires on press, after `delay` and then every `interval` while the trigger is held
+    Repeat { trigger: HoldableTrigger, delay: Duration, interval: Duration },
 }
 
 impl FireTrigger {
-    pub fn from_toml(value: &toml::value::Value) -> Result<FireTrigger, String> {
+    pub fn from_toml(value: &toml::value::Value) -> Result<FireTrigger, ControlsError> {
         use toml::value::Value::*;
         use self::FireTrigger::*;
         use self::MouseWheelDirection::*;
 
-        if let Ok(switch_trigger) = HoldableTrigger::from_toml(value) {
-            Ok(Holdable(switch_trigger))
-        } else {
-            match value {
-                &String(ref s) => match s.as_ref() {
+        match HoldableTrigger::from_toml(value) {
+            Ok(switch_trigger) => Ok(Holdable(switch_trigger)),
+            Err(error) => match value {
+                String(s) => match s.as_ref() {
                     "MouseWheelUp" => Ok(MouseWheelTick(Up)),
                     "MouseWheelDown" => Ok(MouseWheelTick(Down)),
-                    _ => Err(format!("Unknown fire trigger: '{}'", s)),
+                    _ => Err(error),
                 }
-                _ => Err(format!("Fire trigger must be string, got '{}'!", value)),
+                Table(table) => Self::from_toml_table(value, table),
+                _ => Err(error),
+            }
+        }
+    }
+
+    // triggers with settings are tables, named by one of their keys,
+    // e.g. { sequence = ["W", "W"], timeout = 250 }
+    fn from_toml_table(value: &toml::value::Value, table: &toml::value::Table) -> Result<FireTrigger, ControlsError> {
+        use toml::value::Value::*;
+        use self::FireTrigger::*;
+
+        if let Some(steps_value) = table.get("sequence") {
+            let steps = match steps_value {
+                Array(steps) if !steps.is_empty() => steps.iter().enumerate()
+                    .map(|(i, step)| HoldableTrigger::from_toml(step)
+                        .map_err(|e| e.in_path(&format!("sequence[{}]", i))))
+                    .collect::<Result<Vec<_>, _>>()?,
+                v => return Err(unknown_trigger(v).in_path("sequence")),
+            };
+            check_keys(table, &["sequence", "timeout"])?;
+            let timeout = duration_from_toml(table, "timeout", DEFAULT_SEQUENCE_TIMEOUT)?;
+            return Ok(Sequence { steps, timeout });
+        }
+        if let Some(trigger_value) = table.get("release") {
+            check_keys(table, &["release"])?;
+            let trigger = HoldableTrigger::from_toml(trigger_value).map_err(|e| e.in_path("release"))?;
+            return Ok(Release(trigger));
+        }
+        if let Some(trigger_value) = table.get("tap") {
+            check_keys(table, &["tap", "threshold"])?;
+            let trigger = HoldableTrigger::from_toml(trigger_value).map_err(|e| e.in_path("tap"))?;
+            let threshold = duration_from_toml(table, "threshold", DEFAULT_TAP_THRESHOLD)?;
+            return Ok(Tap { trigger, threshold });
+        }
+        if let Some(trigger_value) = table.get("long_press") {
+            check_keys(table, &["long_press", "threshold"])?;
+            let trigger = HoldableTrigger::from_toml(trigger_value).map_err(|e| e.in_path("long_press"))?;
+            let threshold = duration_from_toml(table, "threshold", DEFAULT_LONG_PRESS_THRESHOLD)?;
+            return Ok(LongPress { trigger, threshold });
+        }
+        if let Some(trigger_value) = table.get("repeat") {
+            check_keys(table, &["repeat", "delay", "interval"])?;
+            let trigger = HoldableTrigger::from_toml(trigger_value).map_err(|e| e.in_path("repeat"))?;
+            let delay = duration_from_toml(table, "delay", DEFAULT_REPEAT_DELAY)?;
+            let interval = duration_from_toml(table, "interval", DEFAULT_REPEAT_INTERVAL)?;
+            if interval == Duration::from_secs(0) {
+                return Err(ControlsError::InvalidDuration {
+                    path: "interval".to_string(),
+                    value: table["interval"].clone(),
+                });
             }
+            return Ok(Repeat { trigger, delay, interval });
         }
+        Err(unknown_trigger(value))
     }
 
     pub fn to_toml(&self) -> toml::value::Value {
         use self::FireTrigger::*;
         use super::MouseWheelDirection::*;
 
-        match self {
-            &Holdable(trigger) => trigger.to_toml(),
-            &MouseWheelTick(Up) => toml::value::Value::String(String::from("MouseWheelUp")),
-            &MouseWheelTick(Down) => toml::value::Value::String(String::from("MouseWheelDown")),
+        match *self {
+            Holdable(ref trigger) => trigger.to_toml(),
+            MouseWheelTick(Up) => toml::value::Value::String(String::from("MouseWheelUp")),
+            MouseWheelTick(Down) => toml::value::Value::String(String::from("MouseWheelDown")),
+            Sequence { ref steps, timeout } => {
+                let steps = steps.iter().map(HoldableTrigger::to_toml).collect();
+                let mut table = BTreeMap::new();
+                table.insert(String::from("sequence"), toml::value::Value::Array(steps));
+                table.insert(String::from("timeout"), duration_to_toml(timeout));
+                toml::value::Value::Table(table)
+            },
+            Release(ref trigger) => {
+                let mut table = BTreeMap::new();
+                table.insert(String::from("release"), trigger.to_toml());
+                toml::value::Value::Table(table)
+            },
+            Tap { ref trigger, threshold } => {
+                let mut table = BTreeMap::new();
+                table.insert(String::from("tap"), trigger.to_toml());
+                table.insert(String::from("threshold"), duration_to_toml(threshold));
+                toml::value::Value::Table(table)
+            },
+            LongPress { ref trigger, threshold } => {
+                let mut table = BTreeMap::new();
+                table.insert(String::from("long_press"), trigger.to_toml());
+                table.insert(String::from("threshold"), duration_to_toml(threshold));
+                toml::value::Value::Table(table)
+            },
+            Repeat { ref trigger, delay, interval } => {
+                let mut table = BTreeMap::new();
+                table.insert(String::from("repeat"), trigger.to_toml());
+                table.insert(String::from("delay"), duration_to_toml(delay));
+                table.insert(String::from("interval"), duration_to_toml(interval));
+                toml::value::Value::Table(table)
+            },
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum HoldableTrigger {
     ScanCode(u32),
     KeyCode(VirtualKeyCode),
     Button(u32),
+    GamepadButton(GamepadButton),
+    // active while all of the triggers are held, see HoldableTrigger::chord
+    Chord(BTreeSet<HoldableTrigger>),
+    AxisThreshold(AxisThreshold),
 }
 
 impl HoldableTrigger {
-    pub fn from_toml(value: &toml::value::Value) -> Result<HoldableTrigger, String> {
+    // nested chords are flattened, since a chord of chords is just a bigger chord.
+    // none for less than two different components, that would be a plain trigger
+    pub fn chord<I: IntoIterator<Item = HoldableTrigger>>(triggers: I) -> Option<HoldableTrigger> {
+        let mut components = BTreeSet::new();
+        for trigger in triggers {
+            match trigger {
+                HoldableTrigger::Chord(inner) => components.extend(inner),
+                trigger => { components.insert(trigger); },
+            }
+        }
+        if components.len() < 2 {
+            return None;
+        }
+        Some(HoldableTrigger::Chord(components))
+    }
+
+    pub fn from_toml(value: &toml::value::Value) -> Result<HoldableTrigger, ControlsError> {
         use toml::value::Value::*;
         use self::HoldableTrigger::*;
 
         match value {
-            &Integer(i) => match NumCast::from(i) {
+            Integer(i) => match NumCast::from(*i) {
                 Some(sc) => Ok(ScanCode(sc)),
-                None => return Err(format!("Invalid scan code: {}", i)),
+                None => Err(invalid_id(value)),
             },
-            &String(ref s) => {
-                match AsRef::<str>::as_ref(s) {
-                    // TODO re-add mouse
-                    //"MouseLeft" => Ok(Button(0)),
-                    //"MouseRight" => Ok(Button(1)),
-                    //"MouseMiddle" => Ok(Button(2)),
-                    ss => {
-                        if ss.starts_with("Button") {
-                            match ss[5..].parse() {
-                                Ok(number) => Ok(Button(number)),
-                                Err(_) => Err(format!("Unknown push button {}", s)),
-                            }
-                        } else {
-                            for &(kc, name) in KEY_CODE_PAIRS {
-                                if name == ss {
-                                    return Ok(KeyCode(kc));
-                                }
-                            }
-                            Err(format!("Unknown push button {}", s))
-                        }
-                    }
+            String(s) if s.contains('+') => {
+                s.split('+')
+                    .map(Self::from_name)
+                    .collect::<Option<Vec<_>>>()
+                    .and_then(Self::chord)
+                    .ok_or_else(|| unknown_trigger(value))
+            },
+            String(s) => Self::from_name(s).ok_or_else(|| unknown_trigger(value)),
+            Table(table) if table.contains_key("axis") => {
+                check_keys(table, &["axis", "threshold", "hysteresis"])?;
+                let axis = match table["axis"] {
+                    Integer(i) => NumCast::from(i)
+                        .map(ThresholdAxis::Axis)
+                        .ok_or_else(|| invalid_id(&table["axis"]).in_path("axis"))?,
+                    String(ref name) => GAMEPAD_AXIS_PAIRS.iter()
+                        .find(|&&(_, axis_name)| axis_name == name)
+                        .map(|&(axis, _)| ThresholdAxis::GamepadAxis(axis))
+                        .ok_or_else(|| invalid_id(&table["axis"]).in_path("axis"))?,
+                    ref v => return Err(invalid_id(v).in_path("axis")),
+                };
+                let threshold = match table.get("threshold") {
+                    Some(&Float(threshold)) if threshold != 0.0 => threshold,
+                    Some(v) => return Err(unknown_trigger(v).in_path("threshold")),
+                    None => return Err(unknown_trigger(value)),
+                };
+                let hysteresis = match table.get("hysteresis") {
+                    Some(&Float(hysteresis)) if hysteresis >= 0.0 => hysteresis,
+                    Some(v) => return Err(unknown_trigger(v).in_path("hysteresis")),
+                    None => 0.0,
+                };
+                Ok(AxisThreshold(self::AxisThreshold { axis, threshold, hysteresis }))
+            },
+            _ => Err(unknown_trigger(value))
+        }
+    }
+
+    pub fn to_toml(&self) -> toml::value::Value {
+        use self::HoldableTrigger::*;
+
+        match self {
+            ScanCode(sc) => toml::value::Value::Integer(*sc as i64),
+            Chord(components) => {
+                // modifiers first, that's how people write chords
+                let mut components: Vec<_> = components.iter().collect();
+                components.sort_by_key(|component| !component.is_modifier());
+                let names: Vec<_> = components.iter().map(|component| component.name()).collect();
+                toml::value::Value::String(names.join("+"))
+            },
+            AxisThreshold(threshold) => {
+                let mut table = BTreeMap::new();
+                let axis = match threshold.axis {
+                    ThresholdAxis::Axis(axis) => toml::value::Value::Integer(axis as i64),
+                    ThresholdAxis::GamepadAxis(axis) => toml::value::Value::String(gamepad_axis_name(axis)),
+                };
+                table.insert(String::from("axis"), axis);
+                table.insert(String::from("threshold"), toml::value::Value::Float(threshold.threshold));
+                if threshold.hysteresis != 0.0 {
+                    table.insert(String::from("hysteresis"), toml::value::Value::Float(threshold.hysteresis));
                 }
+                toml::value::Value::Table(table)
+            },
+            trigger => toml::value::Value::String(trigger.name()),
+        }
+    }
+
+    pub(crate) fn has_component(&self, trigger: &HoldableTrigger) -> bool {
+        match self {
+            HoldableTrigger::Chord(components) => components.contains(trigger),
+            _ => false,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<HoldableTrigger> {
+        use self::HoldableTrigger::*;
+
+        // TODO re-add mouse
+        //"MouseLeft" => Ok(Button(0)),
+        //"MouseRight" => Ok(Button(1)),
+        //"MouseMiddle" => Ok(Button(2)),
+        if let Some(number) = name.strip_prefix("Button") {
+            return number.parse().ok().map(Button);
+        }
+        if let Some(number) = name.strip_prefix("ScanCode") {
+            return number.parse().ok().map(ScanCode);
+        }
+        if name.contains(['<', '>']) {
+            return self::AxisThreshold::from_name(name).map(AxisThreshold);
+        }
+        for &(kc, kc_name) in KEY_CODE_PAIRS {
+            if kc_name == name {
+                return Some(KeyCode(kc));
+            }
+        }
+        for &(button, button_name) in GAMEPAD_BUTTON_PAIRS {
+            if button_name == name {
+                return Some(GamepadButton(button));
             }
-            _ => Err(format!("Unknown push button {}", *value))
         }
+        None
     }
 
-    pub fn to_toml(&self) -> toml::value::Value {
+    fn name(&self) -> String {
         use self::HoldableTrigger::*;
 
         match *self {
-            ScanCode(sc) => toml::value::Value::Integer(sc as i64),
+            ScanCode(sc) => format!("ScanCode{}", sc),
             KeyCode(kc) => {
                 for &(key_code, name) in KEY_CODE_PAIRS {
                     if key_code == kc {
-                        return toml::value::Value::String(String::from(name));
+                        return String::from(name);
                     }
                 }
-                toml::value::Value::String(String::new()) // should not happen
+                String::new() // should not happen
             },
             // TODO re-add mouse
-            //Button(0) => toml::value::Value::String(String::from("MouseLeft")),
-            //Button(1) => toml::value::Value::String(String::from("MouseRight")),
-            //Button(2) => toml::value::Value::String(String::from("MouseMiddle")),
-            Button(number) => toml::value::Value::String(format!("Button{}", number)),
+            //Button(0) => String::from("MouseLeft"),
+            //Button(1) => String::from("MouseRight"),
+            //Button(2) => String::from("MouseMiddle"),
+            Button(number) => format!("Button{}", number),
+            GamepadButton(gb) => {
+                for &(button, name) in GAMEPAD_BUTTON_PAIRS {
+                    if button == gb {
+                        return String::from(name);
+                    }
+                }
+                String::new() // should not happen
+            },
+            Chord(_) => String::new(), // chords can't be nested
+            AxisThreshold(threshold) => threshold.name(),
+        }
+    }
+
+    fn is_modifier(&self) -> bool {
+        use super::VirtualKeyCode::*;
+
+        matches!(
+            self,
+            HoldableTrigger::KeyCode(LControl | RControl | LShift | RShift | LAlt | RAlt | LWin | RWin)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ThresholdAxis {
+    Axis(u32),
+    GamepadAxis(GamepadAxis),
+}
+
+// turns an axis into a holdable trigger, that is held while the axis is beyond the threshold
+#[derive(Debug, Clone, Copy)]
+pub struct AxisThreshold {
+    pub axis: ThresholdAxis,
+    // negative thresholds are reached by going below them
+    pub threshold: f64,
+    // once reached, the axis has to get this much closer to 0 than the threshold for a release
+    pub hysteresis: f64,
+}
+
+impl AxisThreshold {
+    pub(crate) fn is_reached(&self, value: f64, was_reached: bool) -> bool {
+        let (value, threshold) = if self.threshold < 0.0 {
+            (-value, -self.threshold)
+        } else {
+            (value, self.threshold)
+        };
+        if was_reached {
+            value > threshold - self.hysteresis
+        } else {
+            value > threshold
+        }
+    }
+
+    // e.g. "Axis2>0.5" or "LeftStickY<-0.5~0.1" with a hysteresis of 0.1, used in chords
+    fn name(&self) -> String {
+        let axis = match self.axis {
+            ThresholdAxis::Axis(axis) => format!("Axis{}", axis),
+            ThresholdAxis::GamepadAxis(axis) => gamepad_axis_name(axis),
+        };
+        let comparison = if self.threshold < 0.0 { '<' } else { '>' };
+        let mut name = format!("{}{}{}", axis, comparison, self.threshold);
+        if self.hysteresis != 0.0 {
+            name.push_str(&format!("~{}", self.hysteresis));
         }
+        name
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        let split = name.find(['<', '>'])?;
+        let (axis_name, rest) = name.split_at(split);
+        let axis = match axis_name.strip_prefix("Axis") {
+            Some(number) => ThresholdAxis::Axis(number.parse().ok()?),
+            None => GAMEPAD_AXIS_PAIRS.iter()
+                .find(|&&(_, name)| name == axis_name)
+                .map(|&(axis, _)| ThresholdAxis::GamepadAxis(axis))?,
+        };
+        let (threshold, hysteresis) = match rest[1..].split_once('~') {
+            Some((threshold, hysteresis)) => (threshold.parse().ok()?, hysteresis.parse().ok()?),
+            None => (rest[1..].parse().ok()?, 0.0),
+        };
+        let below = rest.starts_with('<');
+        if threshold == 0.0 || below != (threshold < 0.0) || hysteresis < 0.0 {
+            return None;
+        }
+        Some(AxisThreshold { axis, threshold, hysteresis })
+    }
+
+    fn key(&self) -> (ThresholdAxis, u64, u64) {
+        (self.axis, self.threshold.to_bits(), self.hysteresis.to_bits())
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// compares the bits of the floats, so that the thresholds can be used as keys
+impl PartialEq for AxisThreshold {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for AxisThreshold {}
+
+impl Hash for AxisThreshold {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl PartialOrd for AxisThreshold {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AxisThreshold {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.axis.cmp(&other.axis)
+            .then(self.threshold.total_cmp(&other.threshold))
+            .then(self.hysteresis.total_cmp(&other.hysteresis))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ValueTrigger {
     MouseX,
     MouseY,
     MouseWheel,
     Axis(u32),
+    GamepadAxis(GamepadAxis),
+    // -1, 0 or 1 depending on which of the two triggers are held,
+    // the ramps are the times it takes to get from 0 to full and back
+    Composite {
+        negative: HoldableTrigger,
+        positive: HoldableTrigger,
+        ramp_up: Duration,
+        ramp_down: Duration,
+    },
 }
 
 impl ValueTrigger {
-    pub fn from_toml(value: &toml::value::Value) -> Result<ValueTrigger, String> {
+    pub fn from_toml(value: &toml::value::Value) -> Result<ValueTrigger, ControlsError> {
         use toml::Value::*;
         use self::ValueTrigger::*;
 
         match value {
-            &Integer(i) => match NumCast::from(i) {
+            Integer(i) => match NumCast::from(*i) {
                 Some(axis) => Ok(Axis(axis)),
-                None => return Err(format!("Invalid axis id: {}", i)),
+                None => Err(invalid_id(value)),
             },
-            &String(ref s) => match s.as_ref() {
+            String(s) => match s.as_ref() {
+                "MouseX" => Ok(MouseX),
+                "MouseY" => Ok(MouseY),
                 "MouseWheel" => Ok(MouseWheel),
-                _ => Err(format!("Unknown axis: '{}'", s)),
+                ss => {
+                    for &(axis, name) in GAMEPAD_AXIS_PAIRS {
+                        if name == ss {
+                            return Ok(GamepadAxis(axis));
+                        }
+                    }
+                    Err(unknown_trigger(value))
+                },
             }
-            v => Err(format!("'axis' must be integer or string, got '{}'!", v)),
+            Table(table) if table.contains_key("negative") && table.contains_key("positive") => {
+                check_keys(table, &["negative", "positive", "ramp_up", "ramp_down"])?;
+                let negative = HoldableTrigger::from_toml(&table["negative"]).map_err(|e| e.in_path("negative"))?;
+                let positive = HoldableTrigger::from_toml(&table["positive"]).map_err(|e| e.in_path("positive"))?;
+                let ramp_up = duration_from_toml(table, "ramp_up", Duration::from_secs(0))?;
+                let ramp_down = duration_from_toml(table, "ramp_down", Duration::from_secs(0))?;
+                Ok(Composite { negative, positive, ramp_up, ramp_down })
+            },
+            _ => Err(unknown_trigger(value)),
         }
     }
 
@@ -144,170 +489,146 @@ impl ValueTrigger {
             MouseY => toml::value::Value::String(String::from("MouseY")),
             MouseWheel => toml::value::Value::String(String::from("MouseWheel")),
             Axis(a) => toml::value::Value::Integer(a as i64),
+            GamepadAxis(ga) => {
+                for &(axis, name) in GAMEPAD_AXIS_PAIRS {
+                    if axis == ga {
+                        return toml::value::Value::String(String::from(name));
+                    }
+                }
+                toml::value::Value::String(String::new()) // should not happen
+            },
+            Composite { ref negative, ref positive, ramp_up, ramp_down } => {
+                let mut table = BTreeMap::new();
+                table.insert(String::from("negative"), negative.to_toml());
+                table.insert(String::from("positive"), positive.to_toml());
+                if ramp_up != Duration::from_secs(0) {
+                    table.insert(String::from("ramp_up"), duration_to_toml(ramp_up));
+                }
+                if ramp_down != Duration::from_secs(0) {
+                    table.insert(String::from("ramp_down"), duration_to_toml(ramp_down));
+                }
+                toml::value::Value::Table(table)
+            },
+        }
+    }
+}
+
+// bound to value targets, but emits both components in one event
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum VectorTrigger {
+    Mouse,
+    Axes(u32, u32),
+    GamepadAxes(GamepadAxis, GamepadAxis),
+    // y is positive for up like on a stick, diagonals have the same length as the other directions
+    Keys { up: HoldableTrigger, down: HoldableTrigger, left: HoldableTrigger, right: HoldableTrigger },
+}
+
+impl VectorTrigger {
+    pub fn from_toml(value: &toml::value::Value) -> Result<VectorTrigger, ControlsError> {
+        use toml::Value::*;
+        use self::VectorTrigger::*;
+        use super::GamepadAxis::*;
+
+        match value {
+            String(s) => match s.as_ref() {
+                "Mouse" => Ok(Mouse),
+                "LeftStick" => Ok(GamepadAxes(LeftStickX, LeftStickY)),
+                "RightStick" => Ok(GamepadAxes(RightStickX, RightStickY)),
+                _ => Err(unknown_trigger(value)),
+            },
+            Table(table) if table.contains_key("x") && table.contains_key("y") => {
+                check_keys(table, &["x", "y"])?;
+                let x = ValueTrigger::from_toml(&table["x"]).map_err(|e| e.in_path("x"))?;
+                let y = ValueTrigger::from_toml(&table["y"]).map_err(|e| e.in_path("y"))?;
+                match (x, y) {
+                    (ValueTrigger::Axis(x), ValueTrigger::Axis(y)) => Ok(Axes(x, y)),
+                    (ValueTrigger::GamepadAxis(x), ValueTrigger::GamepadAxis(y)) => Ok(GamepadAxes(x, y)),
+                    _ => Err(unknown_trigger(&table["y"]).in_path("y")),
+                }
+            },
+            Table(table) if table.contains_key("up") => {
+                check_keys(table, &["up", "down", "left", "right"])?;
+                let direction = |key| match table.get(key) {
+                    Some(v) => HoldableTrigger::from_toml(v).map_err(|e| e.in_path(key)),
+                    None => Err(unknown_trigger(value)),
+                };
+                Ok(Keys {
+                    up: direction("up")?,
+                    down: direction("down")?,
+                    left: direction("left")?,
+                    right: direction("right")?,
+                })
+            },
+            _ => Err(unknown_trigger(value)),
         }
     }
+
+    pub fn to_toml(&self) -> toml::value::Value {
+        use self::VectorTrigger::*;
+        use super::GamepadAxis::*;
+
+        let mut table = BTreeMap::new();
+        match self {
+            Mouse => return toml::value::Value::String(String::from("Mouse")),
+            GamepadAxes(LeftStickX, LeftStickY) => return toml::value::Value::String(String::from("LeftStick")),
+            GamepadAxes(RightStickX, RightStickY) => return toml::value::Value::String(String::from("RightStick")),
+            GamepadAxes(x, y) => {
+                table.insert(String::from("x"), ValueTrigger::GamepadAxis(*x).to_toml());
+                table.insert(String::from("y"), ValueTrigger::GamepadAxis(*y).to_toml());
+            },
+            Axes(x, y) => {
+                table.insert(String::from("x"), ValueTrigger::Axis(*x).to_toml());
+                table.insert(String::from("y"), ValueTrigger::Axis(*y).to_toml());
+            },
+            Keys { up, down, left, right } => {
+                table.insert(String::from("up"), up.to_toml());
+                table.insert(String::from("down"), down.to_toml());
+                table.insert(String::from("left"), left.to_toml());
+                table.insert(String::from("right"), right.to_toml());
+            },
+        }
+        toml::value::Value::Table(table)
+    }
+}
+
+fn unknown_trigger(value: &toml::value::Value) -> ControlsError {
+    ControlsError::UnknownTrigger { path: String::new(), value: value.clone() }
 }
 
-const KEY_CODE_PAIRS: &'static [(VirtualKeyCode, &'static str)] = &[
-    (VirtualKeyCode::Key1, "1"),
-    (VirtualKeyCode::Key2, "2"),
-    (VirtualKeyCode::Key3, "3"),
-    (VirtualKeyCode::Key4, "4"),
-    (VirtualKeyCode::Key5, "5"),
-    (VirtualKeyCode::Key6, "6"),
-    (VirtualKeyCode::Key7, "7"),
-    (VirtualKeyCode::Key8, "8"),
-    (VirtualKeyCode::Key9, "9"),
-    (VirtualKeyCode::Key0, "0"),
-    (VirtualKeyCode::A, "A"),
-    (VirtualKeyCode::B, "B"),
-    (VirtualKeyCode::C, "C"),
-    (VirtualKeyCode::D, "D"),
-    (VirtualKeyCode::E, "E"),
-    (VirtualKeyCode::F, "F"),
-    (VirtualKeyCode::G, "G"),
-    (VirtualKeyCode::H, "H"),
-    (VirtualKeyCode::I, "I"),
-    (VirtualKeyCode::J, "J"),
-    (VirtualKeyCode::K, "K"),
-    (VirtualKeyCode::L, "L"),
-    (VirtualKeyCode::M, "M"),
-    (VirtualKeyCode::N, "N"),
-    (VirtualKeyCode::O, "O"),
-    (VirtualKeyCode::P, "P"),
-    (VirtualKeyCode::Q, "Q"),
-    (VirtualKeyCode::R, "R"),
-    (VirtualKeyCode::S, "S"),
-    (VirtualKeyCode::T, "T"),
-    (VirtualKeyCode::U, "U"),
-    (VirtualKeyCode::V, "V"),
-    (VirtualKeyCode::W, "W"),
-    (VirtualKeyCode::X, "X"),
-    (VirtualKeyCode::Y, "Y"),
-    (VirtualKeyCode::Z, "Z"),
-    (VirtualKeyCode::Escape, "Escape"),
-    (VirtualKeyCode::F1, "F1"),
-    (VirtualKeyCode::F2, "F2"),
-    (VirtualKeyCode::F3, "F3"),
-    (VirtualKeyCode::F4, "F4"),
-    (VirtualKeyCode::F5, "F5"),
-    (VirtualKeyCode::F6, "F6"),
-    (VirtualKeyCode::F7, "F7"),
-    (VirtualKeyCode::F8, "F8"),
-    (VirtualKeyCode::F9, "F9"),
-    (VirtualKeyCode::F10, "F10"),
-    (VirtualKeyCode::F11, "F11"),
-    (VirtualKeyCode::F12, "F12"),
-    (VirtualKeyCode::F13, "F13"),
-    (VirtualKeyCode::F14, "F14"),
-    (VirtualKeyCode::F15, "F15"),
-    (VirtualKeyCode::F16, "F16"),
-    (VirtualKeyCode::F17, "F17"),
-    (VirtualKeyCode::F18, "F18"),
-    (VirtualKeyCode::F19, "F19"),
-    (VirtualKeyCode::F20, "F20"),
-    (VirtualKeyCode::F21, "F21"),
-    (VirtualKeyCode::F22, "F22"),
-    (VirtualKeyCode::F23, "F23"),
-    (VirtualKeyCode::F24, "F24"),
-    (VirtualKeyCode::Snapshot, "Snapshot"),
-    (VirtualKeyCode::Scroll, "Scroll"),
-    (VirtualKeyCode::Pause, "Pause"),
-    (VirtualKeyCode::Insert, "Insert"),
-    (VirtualKeyCode::Home, "Home"),
-    (VirtualKeyCode::Delete, "Delete"),
-    (VirtualKeyCode::End, "End"),
-    (VirtualKeyCode::PageDown, "PageDown"),
-    (VirtualKeyCode::PageUp, "PageUp"),
-    (VirtualKeyCode::Left, "Left"),
-    (VirtualKeyCode::Up, "Up"),
-    (VirtualKeyCode::Right, "Right"),
-    (VirtualKeyCode::Down, "Down"),
-    (VirtualKeyCode::Back, "Back"),
-    (VirtualKeyCode::Return, "Return"),
-    (VirtualKeyCode::Space, "Space"),
-    (VirtualKeyCode::Compose, "Compose"),
-    (VirtualKeyCode::Caret, "Caret"),
-    (VirtualKeyCode::Numlock, "Numlock"),
-    (VirtualKeyCode::Numpad0, "Numpad0"),
-    (VirtualKeyCode::Numpad1, "Numpad1"),
-    (VirtualKeyCode::Numpad2, "Numpad2"),
-    (VirtualKeyCode::Numpad3, "Numpad3"),
-    (VirtualKeyCode::Numpad4, "Numpad4"),
-    (VirtualKeyCode::Numpad5, "Numpad5"),
-    (VirtualKeyCode::Numpad6, "Numpad6"),
-    (VirtualKeyCode::Numpad7, "Numpad7"),
-    (VirtualKeyCode::Numpad8, "Numpad8"),
-    (VirtualKeyCode::Numpad9, "Numpad9"),
-    (VirtualKeyCode::AbntC1, "AbntC1"),
-    (VirtualKeyCode::AbntC2, "AbntC2"),
-    (VirtualKeyCode::Add, "Add"),
-    (VirtualKeyCode::Apostrophe, "Apostrophe"),
-    (VirtualKeyCode::Apps, "Apps"),
-    (VirtualKeyCode::At, "At"),
-    (VirtualKeyCode::Ax, "Ax"),
-    (VirtualKeyCode::Backslash, "Backslash"),
-    (VirtualKeyCode::Calculator, "Calculator"),
-    (VirtualKeyCode::Capital, "Capital"),
-    (VirtualKeyCode::Colon, "Colon"),
-    (VirtualKeyCode::Comma, "Comma"),
-    (VirtualKeyCode::Convert, "Convert"),
-    (VirtualKeyCode::Decimal, "Decimal"),
-    (VirtualKeyCode::Divide, "Divide"),
-    (VirtualKeyCode::Equals, "Equals"),
-    (VirtualKeyCode::Grave, "Grave"),
-    (VirtualKeyCode::Kana, "Kana"),
-    (VirtualKeyCode::Kanji, "Kanji"),
-    (VirtualKeyCode::LAlt, "LAlt"),
-    (VirtualKeyCode::LBracket, "LBracket"),
-    (VirtualKeyCode::LControl, "LControl"),
-    (VirtualKeyCode::LShift, "LShift"),
-    (VirtualKeyCode::LWin, "LWin"),
-    (VirtualKeyCode::Mail, "Mail"),
-    (VirtualKeyCode::MediaSelect, "MediaSelect"),
-    (VirtualKeyCode::MediaStop, "MediaStop"),
-    (VirtualKeyCode::Minus, "Minus"),
-    (VirtualKeyCode::Multiply, "Multiply"),
-    (VirtualKeyCode::Mute, "Mute"),
-    (VirtualKeyCode::MyComputer, "MyComputer"),
-    (VirtualKeyCode::NavigateForward, "NavigateForward"),
-    (VirtualKeyCode::NavigateBackward, "NavigateBackward"),
-    (VirtualKeyCode::NextTrack, "NextTrack"),
-    (VirtualKeyCode::NoConvert, "NoConvert"),
-    (VirtualKeyCode::NumpadComma, "NumpadComma"),
-    (VirtualKeyCode::NumpadEnter, "NumpadEnter"),
-    (VirtualKeyCode::NumpadEquals, "NumpadEquals"),
-    (VirtualKeyCode::OEM102, "OEM102"),
-    (VirtualKeyCode::Period, "Period"),
-    (VirtualKeyCode::PlayPause, "PlayPause"),
-    (VirtualKeyCode::Power, "Power"),
-    (VirtualKeyCode::PrevTrack, "PrevTrack"),
-    (VirtualKeyCode::RAlt, "RAlt"),
-    (VirtualKeyCode::RBracket, "RBracket"),
-    (VirtualKeyCode::RControl, "RControl"),
-    (VirtualKeyCode::RShift, "RShift"),
-    (VirtualKeyCode::RWin, "RWin"),
-    (VirtualKeyCode::Semicolon, "Semicolon"),
-    (VirtualKeyCode::Slash, "Slash"),
-    (VirtualKeyCode::Sleep, "Sleep"),
-    (VirtualKeyCode::Stop, "Stop"),
-    (VirtualKeyCode::Subtract, "Subtract"),
-    (VirtualKeyCode::Sysrq, "Sysrq"),
-    (VirtualKeyCode::Tab, "Tab"),
-    (VirtualKeyCode::Underline, "Underline"),
-    (VirtualKeyCode::Unlabeled, "Unlabeled"),
-    (VirtualKeyCode::VolumeDown, "VolumeDown"),
-    (VirtualKeyCode::VolumeUp, "VolumeUp"),
-    (VirtualKeyCode::Wake, "Wake"),
-    (VirtualKeyCode::WebBack, "WebBack"),
-    (VirtualKeyCode::WebFavorites, "WebFavorites"),
-    (VirtualKeyCode::WebForward, "WebForward"),
-    (VirtualKeyCode::WebHome, "WebHome"),
-    (VirtualKeyCode::WebRefresh, "WebRefresh"),
-    (VirtualKeyCode::WebSearch, "WebSearch"),
-    (VirtualKeyCode::WebStop, "WebStop"),
-    (VirtualKeyCode::Yen, "Yen"),
-    (VirtualKeyCode::Copy, "Copy"),
-    (VirtualKeyCode::Paste, "Paste"),
-    (VirtualKeyCode::Cut, "Cut"),
-];
\ No newline at end of file
+fn invalid_id(value: &toml::value::Value) -> ControlsError {
+    ControlsError::InvalidId { path: String::new(), value: value.clone() }
+}
+
+// durations are given in milliseconds
+fn duration_from_toml(table: &toml::value::Table, key: &str, default: Duration) -> Result<Duration, ControlsError> {
+    match table.get(key) {
+        None => Ok(default),
+        Some(&toml::value::Value::Integer(millis)) if millis >= 0 => Ok(Duration::from_millis(millis as u64)),
+        Some(&toml::value::Value::Float(millis)) if millis >= 0.0 && millis.is_finite() =>
+            Ok(Duration::from_nanos((millis * 1_000_000.0).round() as u64)),
+        Some(v) => Err(ControlsError::InvalidDuration { path: String::from(key), value: v.clone() }),
+    }
+}
+
+// whole milliseconds as integer, anything finer as fractional milliseconds
+fn duration_to_toml(duration: Duration) -> toml::value::Value {
+    if duration.subsec_nanos().is_multiple_of(1_000_000) {
+        toml::value::Value::Integer(duration.as_millis() as i64)
+    } else {
+        toml::value::Value::Float(duration.as_nanos() as f64 / 1_000_000.0)
+    }
+}
+
+fn check_keys(table: &toml::value::Table, known_keys: &[&str]) -> Result<(), ControlsError> {
+    match table.iter().find(|&(key, _)| !known_keys.contains(&key.as_ref())) {
+        Some((key, v)) => Err(unknown_trigger(v).in_path(key)),
+        None => Ok(()),
+    }
+}
+
+fn gamepad_axis_name(axis: GamepadAxis) -> String {
+    GAMEPAD_AXIS_PAIRS.iter()
+        .find(|&&(a, _)| a == axis)
+        .map(|&(_, name)| String::from(name))
+        .unwrap_or_default()
+}
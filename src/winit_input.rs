@@ -0,0 +1,74 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::str::FromStr;
+use std::string::ToString;
+
+use winit::event::DeviceEvent;
+use winit::event::MouseScrollDelta;
+
+use super::Controls;
+use super::DeviceId;
+use super::ElementState;
+use super::InputEvent;
+use super::ValueTargetTrait;
+
+impl From<winit::event::DeviceId> for DeviceId {
+    fn from(device_id: winit::event::DeviceId) -> Self {
+        // winit's ids are opaque, but they are hashable and DefaultHasher::new is deterministic
+        let mut hasher = DefaultHasher::new();
+        device_id.hash(&mut hasher);
+        DeviceId(hasher.finish())
+    }
+}
+
+impl From<winit::event::ElementState> for ElementState {
+    fn from(state: winit::event::ElementState) -> Self {
+        match state {
+            winit::event::ElementState::Pressed => ElementState::Pressed,
+            winit::event::ElementState::Released => ElementState::Released,
+        }
+    }
+}
+
+impl InputEvent {
+    pub fn from_winit(device_event: DeviceEvent) -> Option<Self> {
+        use self::MouseScrollDelta::*;
+
+        match device_event {
+            DeviceEvent::MouseWheel { delta } => match delta { // TODO also handle x and PixelDelta?
+                LineDelta(_x, y) => Some(InputEvent::MouseWheel { delta: y as f64 }),
+                PixelDelta(_) => None,
+            },
+            DeviceEvent::Motion { axis, value } => Some(InputEvent::Motion { axis, value }),
+            DeviceEvent::MouseMotion { delta } => Some(InputEvent::MouseMotion { delta }),
+            DeviceEvent::Button { button, state } => Some(InputEvent::Button {
+                button,
+                state: state.into(),
+            }),
+            DeviceEvent::Key(input) => Some(InputEvent::Key {
+                scan_code: input.scancode,
+                key_code: input.virtual_keycode.map(Into::into),
+                state: input.state.into(),
+            }),
+            DeviceEvent::Removed => Some(InputEvent::Removed),
+            _ => None,
+        }
+    }
+}
+
+impl<FireTarget, SwitchTarget, ValueTarget> Controls<FireTarget, SwitchTarget, ValueTarget>
+where FireTarget: Copy + Eq + Hash + FromStr + ToString,
+      SwitchTarget: Copy + Eq + Hash + FromStr + ToString,
+      ValueTarget: ValueTargetTrait + Copy + Eq + Hash + FromStr + ToString,
+{
+    pub fn process_winit(
+        &mut self,
+        device_id: winit::event::DeviceId,
+        device_event: DeviceEvent,
+    ) {
+        if let Some(event) = InputEvent::from_winit(device_event) {
+            self.process(device_id.into(), event);
+        }
+    }
+}